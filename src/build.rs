@@ -0,0 +1,102 @@
+//! Building a pick tree out of a flat list of options, by repeatedly grouping
+//! options that belong together.
+
+use inquire::{error::InquireResult, MultiSelect, Text};
+
+use crate::{writer, Tree};
+
+/// A group of options, made by a [`Grouper`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub name: String,
+    /// Indices of the options in the group
+    pub members: Vec<usize>,
+}
+
+/// Something that can tell which options belong together
+pub trait Grouper {
+    /// Makes a group out of some of the `options`, which will become a subtree
+    /// of the node at the end of `path`. Returns `None` to stop grouping and keep
+    /// the `options` as they are.
+    fn group(&mut self, path: &[String], options: &[String]) -> InquireResult<Option<Group>>;
+}
+
+/// Asks the user which options belong together with an `inquire::MultiSelect`
+/// prompt, and how to name them with an `inquire::Text` prompt
+pub struct InteractiveGrouper;
+
+impl Grouper for InteractiveGrouper {
+    fn group(&mut self, path: &[String], options: &[String]) -> InquireResult<Option<Group>> {
+        let message = match path.last() {
+            Some(parent) => format!("Which of these in `{}` belong together?", parent),
+            None => "Which of these belong together?".into(),
+        };
+        let members: Vec<usize> = MultiSelect::new(&message, options.to_vec())
+            .with_help_message("space to select, enter to confirm, select nothing to stop grouping")
+            .raw_prompt()?
+            .into_iter()
+            .map(|o| o.index)
+            .collect();
+
+        // Grouping everything together wouldn't help deciding
+        if members.is_empty() || members.len() == options.len() {
+            return Ok(None);
+        }
+
+        let name = Text::new("What should this group be called?")
+            .with_validator(&|s: &str| match s.trim() {
+                "" => Err("The group needs a name".into()),
+                name if !writer::is_representable(&Tree::new(name.into())) => {
+                    Err("That name would be read as a weight, attributes, a link or an include".into())
+                }
+                _ => Ok(()),
+            })
+            .prompt()?;
+        Ok(Some(Group { name: name.trim().into(), members }))
+    }
+}
+
+/// Builds a tree out of `options` with the groups made by `grouper`. Groups with
+/// more than two options are grouped further.
+pub fn build<G: Grouper + ?Sized>(options: Vec<String>, grouper: &mut G) -> InquireResult<Tree> {
+    fn build_children<G: Grouper + ?Sized>(
+        mut options: Vec<String>,
+        path: &mut Vec<String>,
+        grouper: &mut G,
+    ) -> InquireResult<Vec<Tree>> {
+        let mut children = Vec::new();
+        while options.len() > 2 {
+            let group = match grouper.group(path, &options)? {
+                Some(group) => group,
+                None => break,
+            };
+
+            let (members, rest): (Vec<_>, Vec<_>) = options.into_iter()
+                .enumerate()
+                .partition(|(i, _)| group.members.contains(i));
+            options = rest.into_iter().map(|(_, o)| o).collect();
+
+            let mut node = Tree::new(group.name);
+            path.push(node.key.clone());
+            node.children = build_children(members.into_iter().map(|(_, o)| o).collect(), path, grouper)?;
+            path.pop();
+            children.push(node);
+        }
+
+        children.extend(options.into_iter().map(Tree::new));
+        Ok(children)
+    }
+
+    let mut tree = Tree::new("".into());
+    tree.children = build_children(options, &mut Vec::new(), grouper)?;
+    Ok(tree)
+}
+
+/// The options in a flat list, one per line. Blank lines are skipped.
+pub fn read_options(list: &str) -> Vec<String> {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
@@ -0,0 +1,648 @@
+//! Command line parsing. Every subcommand is declared in [`COMMANDS`], which is
+//! what arguments are validated against and what help messages are generated from.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    str::FromStr,
+};
+
+/// A `--long` option, possibly with a `-s`hort alias, that's either a flag or
+/// takes a value
+#[derive(Debug)]
+pub struct Opt {
+    pub long: &'static str,
+    pub short: Option<char>,
+    /// Name of the value this option takes, if it takes one
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+/// A positional argument
+#[derive(Debug)]
+pub struct Arg {
+    pub name: &'static str,
+    pub required: bool,
+    pub help: &'static str,
+    /// What shell completions should suggest for this argument
+    pub complete: Complete,
+}
+
+/// Suggestions for shell completions
+#[derive(Debug)]
+pub enum Complete {
+    /// Identifiers of the existing pick trees
+    Tree,
+    /// Names of the commands
+    Command,
+    /// Paths to files
+    File,
+    /// One of a fixed set of values
+    Choices(&'static [&'static str]),
+}
+
+#[derive(Debug)]
+pub struct Command {
+    pub name: &'static str,
+    pub about: &'static str,
+    pub args: &'static [Arg],
+    pub opts: &'static [Opt],
+}
+
+const TREE_ARG: Arg = Arg {
+    name: "TREE",
+    required: false,
+    help: "Identifier of the pick tree (default: `default`)",
+    complete: Complete::Tree,
+};
+
+const REQUIRED_TREE_ARG: Arg = Arg {
+    name: "TREE",
+    required: true,
+    help: "Identifier of the pick tree",
+    complete: Complete::Tree,
+};
+
+const STRICT_OPT: Opt = Opt {
+    long: "strict",
+    short: None,
+    value: None,
+    help: "Rejects pick trees that mix tabs and spaces for indentation",
+};
+
+const TAB_WIDTH_OPT: Opt = Opt {
+    long: "tab-width",
+    short: None,
+    value: Some("N"),
+    help: "How many spaces a tab is worth when parsing (default: 4)",
+};
+
+const DIR_OPT: Opt = Opt {
+    long: "dir",
+    short: None,
+    value: Some("DIR"),
+    help: "Keeps pick trees in DIR, instead of looking them up in a `.wtp`\n\
+           directory of the project, then in your data directory, then in the\n\
+           system's. Can also be set with $WTP_DIR",
+};
+
+/// The command used when none is given, as in `wtp lunch`
+pub const DEFAULT_COMMAND: &str = "pick";
+
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "pick",
+        about: "Interactively helps you pick one of the options from a tree",
+        args: &[TREE_ARG],
+        opts: &[
+            Opt {
+                long: "full-path",
+                short: Some('F'),
+                value: None,
+                help: "Outputs the whole path to the picked option (e.g. `odd/3`)\n\
+                       instead of just the option itself",
+            },
+            Opt {
+                long: "path",
+                short: Some('p'),
+                value: Some("PATH"),
+                help: "Picks by following PATH instead of asking you, e.g. `odd/3`.\n\
+                       Each step is the name of an option or its index from 0, as\n\
+                       in `1/0`. If PATH stops before a leaf, picking goes on from there",
+            },
+            Opt {
+                long: "search",
+                short: Some('s'),
+                value: None,
+                help: "Starts with a fuzzy search over every option in the tree. Picking\n\
+                       a subtree in the search goes on picking from there",
+            },
+            Opt {
+                long: "random",
+                short: Some('r'),
+                value: None,
+                help: "Picks an option at random instead of asking you, taking the\n\
+                       weights in the tree into account",
+            },
+            Opt {
+                long: "seed",
+                short: None,
+                value: Some("N"),
+                help: "Seeds the --random picks, so they're the same every time",
+            },
+            Opt {
+                long: "count",
+                short: Some('n'),
+                value: Some("N"),
+                help: "Picks N different options, one after the other (default: 1)",
+            },
+            Opt {
+                long: "fresh",
+                short: None,
+                value: Some("N"),
+                help: "Leaves out the options picked in the latest N picks of the\n\
+                       tree, unless that leaves nothing to pick from",
+            },
+            STRICT_OPT,
+            TAB_WIDTH_OPT,
+            DIR_OPT,
+        ],
+    },
+    Command {
+        name: "build",
+        about: "Builds a new pick tree by grouping the options in a flat list",
+        args: &[
+            REQUIRED_TREE_ARG,
+            Arg {
+                name: "FILE",
+                required: false,
+                help: "File with one option per line. Reads from the standard input\n\
+                       if it's `-` or not given",
+                complete: Complete::File,
+            },
+        ],
+        opts: &[
+            Opt {
+                long: "force",
+                short: Some('f'),
+                value: None,
+                help: "Overwrites TREE if it already exists",
+            },
+            DIR_OPT,
+        ],
+    },
+    Command {
+        name: "duel",
+        about: "Ranks every option in a tree by asking which of two is better",
+        args: &[TREE_ARG],
+        opts: &[
+            Opt {
+                long: "full-path",
+                short: Some('F'),
+                value: None,
+                help: "Outputs the whole path to each option instead of just the option",
+            },
+            Opt {
+                long: "save",
+                short: None,
+                value: Some("TREE"),
+                help: "Saves the tree reordered by the ranking as TREE, which may be\n\
+                       the same tree. Comments in the tree aren't kept, and included\n\
+                       trees are written out in full",
+            },
+            STRICT_OPT,
+            TAB_WIDTH_OPT,
+            DIR_OPT,
+        ],
+    },
+    Command {
+        name: "edit",
+        about: "Opens a pick tree in your $EDITOR, creating it if needed",
+        args: &[TREE_ARG],
+        opts: &[DIR_OPT],
+    },
+    Command {
+        name: "new",
+        about: "Creates a new pick tree and opens it in your $EDITOR",
+        args: &[REQUIRED_TREE_ARG],
+        opts: &[DIR_OPT],
+    },
+    Command {
+        name: "rm",
+        about: "Deletes a pick tree",
+        args: &[REQUIRED_TREE_ARG],
+        opts: &[DIR_OPT],
+    },
+    Command {
+        name: "path",
+        about: "Outputs the path to the file of a pick tree",
+        args: &[TREE_ARG],
+        opts: &[DIR_OPT],
+    },
+    Command {
+        name: "list",
+        about: "Lists the pick trees you've created, grouped by namespace",
+        args: &[],
+        opts: &[
+            Opt {
+                long: "flat",
+                short: None,
+                value: None,
+                help: "Outputs the whole identifier of each tree in its own line, such\n\
+                       as `food/lunch`, instead of a tree of namespaces",
+            },
+            DIR_OPT,
+        ],
+    },
+    Command {
+        name: "export",
+        about: "Outputs a pick tree in another format, such as a diagram",
+        args: &[TREE_ARG],
+        opts: &[
+            Opt {
+                long: "format",
+                short: None,
+                value: Some("FORMAT"),
+                help: "`dot` for Graphviz, `mermaid`, or the data formats `json`,\n\
+                       `yaml` and `toml` (default: `dot`)",
+            },
+            STRICT_OPT,
+            TAB_WIDTH_OPT,
+            DIR_OPT,
+        ],
+    },
+    Command {
+        name: "import",
+        about: "Creates a pick tree from a file written by `wtp export`",
+        args: &[
+            REQUIRED_TREE_ARG,
+            Arg {
+                name: "FILE",
+                required: false,
+                help: "File to import. Reads from the standard input if it's `-` or\n\
+                       not given",
+                complete: Complete::File,
+            },
+        ],
+        opts: &[
+            Opt {
+                long: "format",
+                short: None,
+                value: Some("FORMAT"),
+                help: "`json`, `yaml` or `toml` (default: `json`)",
+            },
+            Opt {
+                long: "force",
+                short: Some('f'),
+                value: None,
+                help: "Overwrites TREE if it already exists",
+            },
+            DIR_OPT,
+        ],
+    },
+    Command {
+        name: "fmt",
+        about: "Rewrites a pick tree with consistent indentation, keeping comments",
+        args: &[TREE_ARG],
+        opts: &[
+            Opt {
+                long: "check",
+                short: None,
+                value: None,
+                help: "Doesn't rewrite the tree, but fails if it isn't formatted",
+            },
+            STRICT_OPT,
+            TAB_WIDTH_OPT,
+            DIR_OPT,
+        ],
+    },
+    Command {
+        name: "history",
+        about: "Lists your latest picks",
+        args: &[Arg {
+            name: "TREE",
+            required: false,
+            help: "Only lists picks from this tree",
+            complete: Complete::Tree,
+        }],
+        opts: &[
+            Opt {
+                long: "since",
+                short: None,
+                value: Some("DATE"),
+                help: "Only picks made on or after DATE (YYYY-MM-DD, UTC)",
+            },
+            Opt {
+                long: "until",
+                short: None,
+                value: Some("DATE"),
+                help: "Only picks made on or before DATE (YYYY-MM-DD, UTC)",
+            },
+            Opt {
+                long: "limit",
+                short: None,
+                value: Some("N"),
+                help: "How many picks to list (default: 20)",
+            },
+            DIR_OPT,
+        ],
+    },
+    Command {
+        name: "completions",
+        about: "Outputs a completion script for your shell",
+        args: &[Arg {
+            name: "SHELL",
+            required: true,
+            help: "One of `bash`, `zsh` or `fish`. For example, in bash:\n\
+                   source <(wtp completions bash)",
+            complete: Complete::Choices(crate::completions::SHELLS),
+        }],
+        opts: &[],
+    },
+];
+
+/// The `help` command isn't in [`COMMANDS`] as it's handled by [`parse`] itself
+pub const HELP_COMMAND: Command = Command {
+    name: "help",
+    about: "Shows help about wtp or one of its commands",
+    args: &[Arg {
+        name: "COMMAND",
+        required: false,
+        help: "The command to show help about",
+        complete: Complete::Command,
+    }],
+    opts: &[],
+};
+
+const HELP_OPT: Opt = Opt {
+    long: "help",
+    short: Some('h'),
+    value: None,
+    help: "Shows this message",
+};
+
+const FORMAT_HELP: &str = "\
+PICK_TREE file format:
+    It's a tree where siblings are in the same indentation level and children
+    have more indentation than their parents. Example:
+
+    A node in level 1
+        Some child in level 2
+        Another child in level 2
+    Another node in level 1
+        A child of the node above
+        Another child of that same node
+        Yet another child of that node
+            A node in level 3
+            Another one in level 3
+
+    Blank lines are ignored, and `#` starts a comment when it begins a line or
+    follows a whitespace. Write `\\#` for a `#` that should be part of an option:
+
+    # Where should we have lunch?
+    pizza       # the one around the corner
+    \\#1 burgers
+
+    An option can be made more or less likely to be picked with --random by
+    giving it a weight, which is 1 by default:
+
+    pizza [w=3]
+    salad [w=0.5]
+
+    Options can be tagged with attributes instead of being grouped by hand.
+    wtp then asks about the attribute that best tells the options apart, such
+    as \"cuisine?\" and then \"price?\", until a single option is left:
+
+    Sushi {cuisine: japanese, price: $$, distance: far}
+    Ramen {cuisine: japanese, price: $, distance: near}
+    Pizza {cuisine: italian, price: $, distance: near}
+
+    Lists shared by several trees can be kept in a tree of their own, and
+    spliced into others with `@include`, which takes the place of that tree's
+    top-level options:
+
+    lunch
+        @include restaurants
+    dinner
+        @include restaurants
+        cook something
+
+    A line starting with `->` links to another tree, or to an option in it,
+    which is only read when the link is picked:
+
+    watch
+        -> movies/comedy
+        -> series
+
+    Tabs and spaces may be mixed, with a tab being worth --tab-width spaces,
+    unless --strict is given.
+";
+
+/// Finds a command by its name
+pub fn find(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|c| c.name == name)
+}
+
+/// The arguments given to a command
+pub struct Matches {
+    pub command: &'static Command,
+    args: Vec<String>,
+    flags: HashSet<&'static str>,
+    values: HashMap<&'static str, String>,
+}
+
+impl Matches {
+    /// The `i`-th positional argument
+    pub fn arg(&self, i: usize) -> Option<&str> {
+        self.args.get(i).map(String::as_str)
+    }
+
+    /// Whether the flag `--long` was given
+    pub fn flag(&self, long: &str) -> bool {
+        self.flags.contains(long)
+    }
+
+    /// Value of the option `--long`
+    pub fn value(&self, long: &str) -> Option<&str> {
+        self.values.get(long).map(String::as_str)
+    }
+
+    /// Value of the option `--long`, parsed. `expected` describes the values
+    /// that are accepted, for the error message.
+    pub fn parsed<T: FromStr>(&self, long: &str, expected: &str) -> Result<Option<T>, UsageError> {
+        self.value(long)
+            .map(|v| v.parse().map_err(|_| UsageError {
+                message: format!("--{} expects {}, got `{}`", long, expected, v),
+                command: Some(self.command),
+            }))
+            .transpose()
+    }
+}
+
+/// What the command line asks for
+pub enum Parsed {
+    Run(Matches),
+    /// Help about a command, or about `wtp` itself
+    Help(Option<&'static Command>),
+    Version,
+}
+
+/// The command line doesn't make sense
+#[derive(Debug)]
+pub struct UsageError {
+    pub message: String,
+    /// The command whose usage should be suggested
+    pub command: Option<&'static Command>,
+}
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        match self.command {
+            Some(c) => write!(f, "\n\nSee `wtp help {}` for more information.", c.name),
+            None => write!(f, "\n\nSee `wtp help` for more information."),
+        }
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+/// Parses the command line arguments, without the program name
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Parsed, UsageError> {
+    let mut args = args.into_iter().peekable();
+
+    let command = match args.peek().map(String::as_str) {
+        Some("-h" | "--help") => return Ok(Parsed::Help(None)),
+        Some("-V" | "--version") => return Ok(Parsed::Version),
+        Some("help") => {
+            args.next();
+            return match args.next() {
+                None => Ok(Parsed::Help(None)),
+                Some(name) => find(&name)
+                    .map(|c| Parsed::Help(Some(c)))
+                    .ok_or_else(|| UsageError {
+                        message: format!("unknown command `{}`", name),
+                        command: None,
+                    }),
+            };
+        }
+        Some(name) => match find(name) {
+            Some(command) => {
+                args.next();
+                command
+            }
+            None => find(DEFAULT_COMMAND).unwrap(),
+        },
+        None => find(DEFAULT_COMMAND).unwrap(),
+    };
+
+    let error = |message: String| UsageError { message, command: Some(command) };
+
+    let mut matches = Matches {
+        command,
+        args: Vec::new(),
+        flags: HashSet::new(),
+        values: HashMap::new(),
+    };
+    let mut only_positionals = false;
+    while let Some(arg) = args.next() {
+        if only_positionals || arg == "-" || !arg.starts_with('-') {
+            if matches.args.len() == command.args.len() {
+                return Err(error(format!("unexpected argument `{}`", arg)));
+            }
+            matches.args.push(arg);
+            continue;
+        }
+        if arg == "--" {
+            only_positionals = true;
+            continue;
+        }
+        if arg == "-h" || arg == "--help" {
+            return Ok(Parsed::Help(Some(command)));
+        }
+
+        // Split `--name=value`
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if arg.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let opt = command.opts.iter()
+            .find(|o| match name.strip_prefix("--") {
+                Some(long) => o.long == long,
+                None => {
+                    let mut short = name[1..].chars();
+                    o.short.is_some() && short.next() == o.short && short.next().is_none()
+                }
+            })
+            .ok_or_else(|| error(format!("unknown option `{}` for `wtp {}`", name, command.name)))?;
+
+        match (opt.value, inline_value) {
+            (None, None) => { matches.flags.insert(opt.long); }
+            (None, Some(_)) => {
+                return Err(error(format!("--{} doesn't take a value", opt.long)));
+            }
+            (Some(_), Some(value)) => { matches.values.insert(opt.long, value); }
+            (Some(value_name), None) => {
+                let value = args.next()
+                    .ok_or_else(|| error(format!("--{} expects a value {}", opt.long, value_name)))?;
+                matches.values.insert(opt.long, value);
+            }
+        }
+    }
+
+    if let Some(missing) = command.args.iter().skip(matches.args.len()).find(|a| a.required) {
+        return Err(error(format!("missing argument {}", missing.name)));
+    }
+
+    Ok(Parsed::Run(matches))
+}
+
+/// Indents every line but the first of a help text by `indent` spaces
+fn indent_lines(text: &str, indent: usize) -> String {
+    text.replace('\n', &format!("\n{}", " ".repeat(indent)))
+}
+
+fn usage(command: &Command) -> String {
+    let mut usage = format!("wtp {}", command.name);
+    if !command.opts.is_empty() {
+        usage.push_str(" [OPTIONS]");
+    }
+    for arg in command.args {
+        if arg.required {
+            usage += &format!(" {}", arg.name);
+        } else {
+            usage += &format!(" [{}]", arg.name);
+        }
+    }
+    usage
+}
+
+/// Help message about `command`, or about `wtp` itself if it's `None`
+pub fn help(command: Option<&Command>) -> String {
+    let command = match command {
+        Some(command) => command,
+        None => {
+            let mut help = format!(
+                "wtp - What To Pick?\nDecision trees to help humans decide stuff\nv{}\n\n\
+                 USAGE:\n    wtp COMMAND [OPTIONS] [ARGS]\n    wtp [OPTIONS] [TREE]    (same as `wtp {} ...`)\n    wtp --version\n\n\
+                 COMMANDS:\n",
+                env!("CARGO_PKG_VERSION"),
+                DEFAULT_COMMAND,
+            );
+            let width = COMMANDS.iter().map(|c| c.name.len()).max().unwrap_or(0);
+            for c in COMMANDS {
+                help += &format!("    {:width$}  {}\n", c.name, c.about, width = width);
+            }
+            help += &format!("    {:width$}  {}\n", HELP_COMMAND.name, HELP_COMMAND.about, width = width);
+            help += "\nRun `wtp help COMMAND` to see the options of a command.\n\n";
+            help += FORMAT_HELP;
+            return help;
+        }
+    };
+
+    let mut help = format!("wtp {} - {}\n\nUSAGE:\n    {}\n", command.name, command.about, usage(command));
+
+    if !command.args.is_empty() {
+        let width = command.args.iter().map(|a| a.name.len()).max().unwrap_or(0);
+        help += "\nARGS:\n";
+        for arg in command.args {
+            help += &format!("    {:width$}  {}\n", arg.name, indent_lines(arg.help, width + 6), width = width);
+        }
+    }
+
+    let opt_names: Vec<(String, &Opt)> = command.opts.iter()
+        .chain(std::iter::once(&HELP_OPT))
+        .map(|o| {
+            let short = o.short.map(|s| format!("-{}, ", s)).unwrap_or_else(|| "    ".into());
+            let value = o.value.map(|v| format!(" {}", v)).unwrap_or_default();
+            (format!("{}--{}{}", short, o.long, value), o)
+        })
+        .collect();
+    let width = opt_names.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+    help += "\nOPTIONS:\n";
+    for (name, opt) in &opt_names {
+        help += &format!("    {:width$}  {}\n", name, indent_lines(opt.help, width + 6), width = width);
+    }
+
+    help
+}
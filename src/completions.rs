@@ -0,0 +1,351 @@
+//! Shell completion scripts, generated from the commands in [`cli::COMMANDS`].
+//! Tree identifiers are completed dynamically by running `wtp list`.
+
+use std::iter;
+
+use crate::cli::{self, Command, Complete, Opt};
+
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// The completion script for `shell`, if it's one of [`SHELLS`]
+pub fn script(shell: &str) -> Option<String> {
+    match shell {
+        "bash" => Some(bash()),
+        "zsh" => Some(zsh()),
+        "fish" => Some(fish()),
+        _ => None,
+    }
+}
+
+fn commands() -> impl Iterator<Item = &'static Command> {
+    cli::COMMANDS.iter().chain(iter::once(&cli::HELP_COMMAND))
+}
+
+fn default_command() -> &'static Command {
+    cli::find(cli::DEFAULT_COMMAND).unwrap()
+}
+
+/// A help text in a single line
+fn summary(help: &str) -> String {
+    help.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn opt_words(opt: &Opt) -> Vec<String> {
+    opt.short.map(|s| format!("-{}", s)).into_iter()
+        .chain(iter::once(format!("--{}", opt.long)))
+        .collect()
+}
+
+/// Words an argument can be completed with, as a shell command substitution or
+/// a list of words
+fn bash_arg_words(complete: &Complete) -> String {
+    match complete {
+        Complete::Tree => "$(wtp list --flat 2>/dev/null)".into(),
+        Complete::Command => commands().map(|c| c.name).collect::<Vec<_>>().join(" "),
+        Complete::File => "$(compgen -f -- \"$cur\")".into(),
+        Complete::Choices(choices) => choices.join(" "),
+    }
+}
+
+/// Options of any command that take a value, such as `--dir`
+fn value_opts() -> Vec<String> {
+    let mut opts: Vec<String> = Vec::new();
+    for word in commands().flat_map(|c| c.opts).filter(|o| o.value.is_some()).flat_map(opt_words) {
+        if !opts.contains(&word) {
+            opts.push(word);
+        }
+    }
+    opts
+}
+
+fn bash() -> String {
+    let command_names: Vec<&str> = commands().map(|c| c.name).collect();
+    let value_opts = value_opts();
+
+    let mut cases = String::new();
+    for command in commands() {
+        let opts: Vec<String> = command.opts.iter().flat_map(opt_words).collect();
+        let mut positions = String::new();
+        for (i, arg) in command.args.iter().enumerate() {
+            positions += &format!("                {}) words=\"{}\" ;;\n", i, bash_arg_words(&arg.complete));
+        }
+        cases += &format!(
+            "        {})\n            opts=\"{} --help\"\n            case $pos in\n{}            esac\n            ;;\n",
+            command.name, opts.join(" "), positions,
+        );
+
+        // Without a command yet, the first argument is a command or a tree to
+        // pick from
+        if command.name == cli::DEFAULT_COMMAND {
+            let words = command.args.first().map(|a| bash_arg_words(&a.complete)).unwrap_or_default();
+            cases += &format!(
+                "        \"\")\n            opts=\"{} --help\"\n            words=\"{} {}\"\n            ;;\n",
+                opts.join(" "), command_names.join(" "), words,
+            );
+        }
+    }
+
+    format!(r#"# bash completion for wtp
+# Load it with `source <(wtp completions bash)`
+
+_wtp() {{
+    local cur prev cmd pos opts words i
+    COMPREPLY=()
+    cur="${{COMP_WORDS[COMP_CWORD]}}"
+    prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    words=""
+
+    # The command, and the position of the argument being completed. Without
+    # a command, the first argument is a tree to pick from.
+    cmd=""
+    pos=0
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        case "${{COMP_WORDS[i]}}" in
+            {value_opts})
+                ((i++))
+                ;;
+            -*)
+                ;;
+            *)
+                if [[ -z "$cmd" ]]; then
+                    case "${{COMP_WORDS[i]}}" in
+                        {commands})
+                            cmd="${{COMP_WORDS[i]}}"
+                            continue
+                            ;;
+                    esac
+                    cmd="{default}"
+                fi
+                ((pos++))
+                ;;
+        esac
+    done
+
+    # Options that take a value can't be completed
+    case "$prev" in
+        {value_opts})
+            return
+            ;;
+    esac
+
+    case "$cmd" in
+{cases}    esac
+
+    if [[ "$cur" == -* ]]; then
+        COMPREPLY=($(compgen -W "$opts" -- "$cur"))
+    else
+        COMPREPLY=($(compgen -W "$words" -- "$cur"))
+    fi
+}}
+
+complete -F _wtp wtp
+"#,
+        commands = command_names.join("|"),
+        value_opts = value_opts.join("|"),
+        default = cli::DEFAULT_COMMAND,
+        cases = cases,
+    )
+}
+
+/// Escapes text for zsh `_arguments` and `_describe` specs inside single quotes
+fn zsh_escape(s: &str) -> String {
+    s.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh_arg_action(complete: &Complete) -> String {
+    match complete {
+        Complete::Tree => "_wtp_trees".into(),
+        Complete::Command => "_wtp_commands".into(),
+        Complete::File => "_files".into(),
+        Complete::Choices(choices) => format!("({})", choices.join(" ")),
+    }
+}
+
+/// `_arguments` specs for the options and arguments of `command`
+fn zsh_specs(command: &Command, with_args: bool) -> String {
+    let mut specs = Vec::new();
+    for opt in command.opts {
+        let help = zsh_escape(&summary(opt.help));
+        let value = opt.value.map(|v| format!(":{}: ", v)).unwrap_or_default();
+        match opt.short {
+            Some(s) => specs.push(format!(
+                "'(-{s} --{l})'{{-{s},--{l}}}'[{h}]{v}'",
+                s = s, l = opt.long, h = help, v = value,
+            )),
+            None => specs.push(format!("'--{}[{}]{}'", opt.long, help, value)),
+        }
+    }
+    specs.push("'(- *)'{-h,--help}'[Shows help]'".into());
+    if with_args {
+        for arg in command.args {
+            let optional = if arg.required { "" } else { ":" };
+            specs.push(format!("':{}{}:{}'", optional, arg.name, zsh_arg_action(&arg.complete)));
+        }
+    }
+    specs.join(" \\\n                        ")
+}
+
+fn zsh() -> String {
+    let descriptions: Vec<String> = commands()
+        .map(|c| format!("        '{}:{}'", c.name, zsh_escape(c.about)))
+        .collect();
+
+    let mut cases = String::new();
+    for command in commands() {
+        cases += &format!(
+            "                {})\n                    _arguments {}\n                    ;;\n",
+            command.name, zsh_specs(command, true),
+        );
+    }
+    // Without a command, the first word is a tree and the rest are pick options
+    cases += &format!(
+        "                *)\n                    _arguments {}\n                    ;;\n",
+        zsh_specs(default_command(), false),
+    );
+
+    format!(r#"#compdef wtp
+# zsh completion for wtp
+# Load it with `source <(wtp completions zsh)`, or save it as `_wtp` in your $fpath
+
+_wtp_trees() {{
+    local -a trees
+    trees=(${{(f)"$(wtp list --flat 2>/dev/null)"}})
+    _describe -t trees 'pick tree' trees
+}}
+
+_wtp_commands() {{
+    local -a commands
+    commands=(
+{descriptions}
+    )
+    _describe -t commands 'command' commands
+}}
+
+_wtp() {{
+    local line state
+
+    _arguments -C \
+        '(- *)'{{-h,--help}}'[Shows help]' \
+        '(- *)'{{-V,--version}}'[Shows the version]' \
+        '1: :->command' \
+        '*:: :->args'
+
+    case $state in
+        command)
+            _alternative 'commands: :_wtp_commands' 'trees: :_wtp_trees'
+            ;;
+        args)
+            case $line[1] in
+{cases}            esac
+            ;;
+    esac
+}}
+
+if [ "$funcstack[1]" = "_wtp" ]; then
+    _wtp "$@"
+else
+    compdef _wtp wtp
+fi
+"#,
+        descriptions = descriptions.join("\n"),
+        cases = cases,
+    )
+}
+
+/// Quotes text for fish
+fn fish_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn fish() -> String {
+    let names: Vec<&str> = commands().map(|c| c.name).collect();
+    let value_opts = value_opts();
+
+    let mut script = String::from(
+        "# fish completion for wtp\n\
+         # Load it with `wtp completions fish | source`\n\n\
+         function __wtp_trees\n    wtp list --flat 2>/dev/null\nend\n\n",
+    );
+    // Position of the argument being completed, not counting the command
+    script += &format!(
+        "function __wtp_position\n\
+         \x20   set -l tokens (commandline -opc)\n\
+         \x20   set -e tokens[1]\n\
+         \x20   set -l pos 0\n\
+         \x20   set -l skip 0\n\
+         \x20   set -l command 0\n\
+         \x20   for token in $tokens\n\
+         \x20       if test $skip = 1\n\
+         \x20           set skip 0\n\
+         \x20           continue\n\
+         \x20       end\n\
+         \x20       switch $token\n\
+         \x20           case {value_opts}\n\
+         \x20               set skip 1\n\
+         \x20           case '-*'\n\
+         \x20           case {names}\n\
+         \x20               if test $pos = 0; and test $command = 0\n\
+         \x20                   set command 1\n\
+         \x20               else\n\
+         \x20                   set pos (math $pos + 1)\n\
+         \x20               end\n\
+         \x20           case '*'\n\
+         \x20               set pos (math $pos + 1)\n\
+         \x20       end\n\
+         \x20   end\n\
+         \x20   echo $pos\n\
+         end\n\n",
+        value_opts = value_opts.join(" "),
+        names = names.join(" "),
+    );
+    script += "complete -c wtp -f\n\
+         complete -c wtp -n __fish_use_subcommand -s h -l help -d 'Shows help'\n\
+         complete -c wtp -n __fish_use_subcommand -s V -l version -d 'Shows the version'\n";
+
+    // Commands can only come first, before any tree to pick from
+    let no_command = fish_quote(&format!(
+        "not __fish_seen_subcommand_from {}; and test (__wtp_position) = 0",
+        names.join(" "),
+    ));
+    for command in commands() {
+        script += &format!(
+            "complete -c wtp -n {} -a {} -d {}\n",
+            no_command, command.name, fish_quote(command.about),
+        );
+    }
+
+    for command in commands() {
+        // The default command's options are also valid without any command
+        let condition = if command.name == cli::DEFAULT_COMMAND {
+            let others: Vec<&str> = names.iter().copied().filter(|n| *n != command.name).collect();
+            format!("not __fish_seen_subcommand_from {}", others.join(" "))
+        } else {
+            format!("__fish_seen_subcommand_from {}", command.name)
+        };
+
+        script += "\n";
+        for opt in command.opts {
+            let short = opt.short.map(|s| format!(" -s {}", s)).unwrap_or_default();
+            let value = if opt.value.is_some() { " -x" } else { "" };
+            script += &format!(
+                "complete -c wtp -n {}{} -l {}{} -d {}\n",
+                fish_quote(&condition), short, opt.long, value, fish_quote(&summary(opt.help)),
+            );
+        }
+        for (i, arg) in command.args.iter().enumerate() {
+            let words = match &arg.complete {
+                Complete::Tree => "-a '(__wtp_trees)'".into(),
+                Complete::Command => format!("-a {}", fish_quote(&names.join(" "))),
+                Complete::File => "-F".into(),
+                Complete::Choices(choices) => format!("-a {}", fish_quote(&choices.join(" "))),
+            };
+            let condition = format!("{}; and test (__wtp_position) = {}", condition, i);
+            script += &format!("complete -c wtp -n {} {}\n", fish_quote(&condition), words);
+        }
+    }
+
+    script
+}
@@ -0,0 +1,108 @@
+//! Ranking the leaves of a pick tree by asking which of two of them is better,
+//! over and over, as in a tournament.
+//!
+//! The leaves are merge sorted, so ranking `n` of them takes about `n log n`
+//! duels.
+
+use inquire::{error::InquireResult, Select};
+
+use crate::{tree::PATH_SEPARATOR, LinkError, Tree};
+
+/// Decides who wins a duel between two leaves, given by their paths
+pub trait Judge {
+    /// Whether `a` is better than `b`
+    fn prefers(&mut self, a: &[String], b: &[String]) -> InquireResult<bool>;
+}
+
+/// Asks the user to settle each duel with an `inquire::Select` prompt
+#[derive(Default)]
+pub struct InteractiveJudge {
+    duels: usize,
+}
+
+impl Judge for InteractiveJudge {
+    fn prefers(&mut self, a: &[String], b: &[String]) -> InquireResult<bool> {
+        self.duels += 1;
+        let message = format!("Duel #{}: which one is better?", self.duels);
+        let separator = PATH_SEPARATOR.to_string();
+        let options = vec![a.join(&separator), b.join(&separator)];
+        let res = Select::new(&message, options)
+            .with_vim_mode(true)
+            .raw_prompt()?;
+        Ok(res.index == 0)
+    }
+}
+
+/// Ranks `items` from best to worst with the duels settled by `judge`
+pub fn rank<J: Judge + ?Sized>(mut items: Vec<Vec<String>>, judge: &mut J) -> InquireResult<Vec<Vec<String>>> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+
+    let right = items.split_off(items.len() / 2);
+    let left = rank(items, judge)?;
+    let right = rank(right, judge)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        if judge.prefers(a, b)? {
+            merged.extend(left.next());
+        } else {
+            merged.extend(right.next());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+/// Paths to every leaf in `tree`, in order, including the leaves its links lead to
+pub fn leaves(tree: &Tree) -> Result<Vec<Vec<String>>, LinkError> {
+    let mut tree = tree.clone();
+    tree.follow_all_links()?;
+    Ok(tree.nodes()
+        .into_iter()
+        .filter(|(_, node)| node.is_leaf())
+        .map(|(path, _)| path)
+        .collect())
+}
+
+/// A copy of `tree` where siblings are sorted by the best rank among their leaves
+/// in `ranking`, best first. Links rank as the best of the leaves they lead to,
+/// and leaves missing from `ranking` go last.
+pub fn reorder(tree: &Tree, ranking: &[Vec<String>]) -> Tree {
+    fn best_rank(node: &Tree, path: &mut Vec<String>, ranking: &[Vec<String>]) -> usize {
+        if node.is_leaf() {
+            return ranking.iter().position(|p| p.starts_with(path)).unwrap_or(usize::MAX);
+        }
+        node.children.iter()
+            .map(|c| {
+                path.push(c.key.clone());
+                let rank = best_rank(c, path, ranking);
+                path.pop();
+                rank
+            })
+            .min()
+            .unwrap_or(usize::MAX)
+    }
+
+    fn sort(node: &mut Tree, path: &mut Vec<String>, ranking: &[Vec<String>]) {
+        for child in &mut node.children {
+            path.push(child.key.clone());
+            sort(child, path, ranking);
+            path.pop();
+        }
+        node.children.sort_by_cached_key(|c| {
+            path.push(c.key.clone());
+            let rank = best_rank(c, path, ranking);
+            path.pop();
+            rank
+        });
+    }
+
+    let mut tree = tree.clone();
+    sort(&mut tree, &mut Vec::new(), ranking);
+    tree
+}
@@ -0,0 +1,209 @@
+//! Exporting pick trees to other formats, such as graphs that can be rendered
+//! as diagrams, or data formats that other tools can read.
+//!
+//! In the data formats, every node is an object with its `key`, its `children`,
+//! and its `weight`, `attributes` and `link` target when it has any:
+//!
+//! ```json
+//! {
+//!   "key": "",
+//!   "children": [
+//!     { "key": "pizza", "weight": 3, "children": [] },
+//!     { "key": "sushi", "attributes": { "cuisine": "japanese" }, "children": [] }
+//!   ]
+//! }
+//! ```
+//!
+//! See [`crate::import`] for reading them back.
+
+use std::fmt::Write;
+
+use crate::{json, Tree};
+
+/// Formats a tree can be exported to
+pub const FORMATS: &[&str] = &["dot", "mermaid", "json", "yaml", "toml"];
+
+/// Exports `tree` to `format`, one of [`FORMATS`]. `name` labels the root in
+/// diagrams.
+pub fn export(tree: &Tree, name: &str, format: &str) -> Option<String> {
+    match format {
+        "dot" => Some(to_dot(tree, name)),
+        "mermaid" => Some(to_mermaid(tree, name)),
+        "json" => Some(to_json(tree)),
+        "yaml" => Some(to_yaml(tree)),
+        "toml" => Some(to_toml(tree)),
+        _ => None,
+    }
+}
+
+/// Every node in `tree`, root included, with a stable id given by its position
+/// in preorder, and the id of its parent
+fn numbered(tree: &Tree) -> Vec<(usize, Option<usize>, &Tree)> {
+    fn visit<'a>(t: &'a Tree, parent: Option<usize>, nodes: &mut Vec<(usize, Option<usize>, &'a Tree)>) {
+        let id = nodes.len();
+        nodes.push((id, parent, t));
+        for child in &t.children {
+            visit(child, Some(id), nodes);
+        }
+    }
+
+    let mut nodes = Vec::new();
+    visit(tree, None, &mut nodes);
+    nodes
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// The tree as a Graphviz DOT digraph
+pub fn to_dot(tree: &Tree, name: &str) -> String {
+    let mut out = format!("digraph \"{}\" {{\n", escape_dot(name));
+    out += "    rankdir=LR;\n";
+    out += "    node [shape=box];\n";
+    for (id, parent, node) in numbered(tree) {
+        let label = if parent.is_none() { name } else { &node.key };
+        let shape = if parent.is_none() { " shape=ellipse" } else { "" };
+        writeln!(out, "    n{} [label=\"{}\"{}];", id, escape_dot(label), shape).unwrap();
+        if let Some(parent) = parent {
+            writeln!(out, "    n{} -> n{};", parent, id).unwrap();
+        }
+    }
+    out += "}\n";
+    out
+}
+
+/// Mermaid labels can't hold some characters, which are written as entity codes
+fn escape_mermaid(s: &str) -> String {
+    s.replace('#', "#35;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace('\n', " ")
+}
+
+/// The tree as a Mermaid flowchart
+pub fn to_mermaid(tree: &Tree, name: &str) -> String {
+    let mut out = String::from("flowchart LR\n");
+    for (id, parent, node) in numbered(tree) {
+        match parent {
+            None => writeln!(out, "    n{}([\"{}\"])", id, escape_mermaid(name)).unwrap(),
+            Some(parent) => {
+                writeln!(out, "    n{}[\"{}\"]", id, escape_mermaid(&node.key)).unwrap();
+                writeln!(out, "    n{} --> n{}", parent, id).unwrap();
+            }
+        }
+    }
+    out
+}
+
+/// The tree as JSON, indented with two spaces
+pub fn to_json(tree: &Tree) -> String {
+    fn write_node(t: &Tree, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth + 1);
+        write!(out, "{{\n{}\"key\": {}", indent, json::quote(&t.key)).unwrap();
+        if let Some(link) = &t.link {
+            write!(out, ",\n{}\"link\": {}", indent, json::quote(&link.target)).unwrap();
+        }
+        if let Some(weight) = t.weight {
+            write!(out, ",\n{}\"weight\": {}", indent, json::number(weight)).unwrap();
+        }
+        if !t.attributes.is_empty() {
+            let attributes: Vec<String> = t.attributes.iter()
+                .map(|(name, value)| format!("{}: {}", json::quote(name), json::quote(value)))
+                .collect();
+            write!(out, ",\n{}\"attributes\": {{ {} }}", indent, attributes.join(", ")).unwrap();
+        }
+        write!(out, ",\n{}\"children\": [", indent).unwrap();
+        for (i, child) in t.children.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            write!(out, "{}\n{}  ", separator, indent).unwrap();
+            write_node(child, depth + 2, out);
+        }
+        if !t.children.is_empty() {
+            write!(out, "\n{}", indent).unwrap();
+        }
+        write!(out, "]\n{}}}", "  ".repeat(depth)).unwrap();
+    }
+
+    let mut out = String::new();
+    write_node(tree, 0, &mut out);
+    out.push('\n');
+    out
+}
+
+/// The tree as YAML. Strings are always double-quoted, so they're never mistaken
+/// for numbers or booleans.
+pub fn to_yaml(tree: &Tree) -> String {
+    fn write_node(t: &Tree, indent: &str, out: &mut String) {
+        // The first line follows the `- ` of the parent's sequence
+        writeln!(out, "key: {}", json::quote(&t.key)).unwrap();
+        if let Some(link) = &t.link {
+            writeln!(out, "{}link: {}", indent, json::quote(&link.target)).unwrap();
+        }
+        if let Some(weight) = t.weight {
+            writeln!(out, "{}weight: {}", indent, json::number(weight)).unwrap();
+        }
+        if !t.attributes.is_empty() {
+            writeln!(out, "{}attributes:", indent).unwrap();
+            for (name, value) in &t.attributes {
+                writeln!(out, "{}  {}: {}", indent, json::quote(name), json::quote(value)).unwrap();
+            }
+        }
+        if t.children.is_empty() {
+            writeln!(out, "{}children: []", indent).unwrap();
+        } else {
+            writeln!(out, "{}children:", indent).unwrap();
+            for child in &t.children {
+                write!(out, "{}  - ", indent).unwrap();
+                write_node(child, &format!("{}    ", indent), out);
+            }
+        }
+    }
+
+    let mut out = String::new();
+    write_node(tree, "", &mut out);
+    out
+}
+
+/// The tree as TOML. Children are arrays of tables, so a node's children come
+/// in `[[children.children]]` tables after it.
+pub fn to_toml(tree: &Tree) -> String {
+    fn write_fields(t: &Tree, out: &mut String) {
+        writeln!(out, "key = {}", json::quote(&t.key)).unwrap();
+        if let Some(link) = &t.link {
+            writeln!(out, "link = {}", json::quote(&link.target)).unwrap();
+        }
+        if let Some(weight) = t.weight {
+            writeln!(out, "weight = {}", json::number(weight)).unwrap();
+        }
+    }
+
+    fn write_children(t: &Tree, table: &str, out: &mut String) {
+        for child in &t.children {
+            writeln!(out, "\n[[{}]]", table).unwrap();
+            write_fields(child, out);
+            write_attributes(child, table, out);
+            write_children(child, &format!("{}.children", table), out);
+        }
+    }
+
+    fn write_attributes(t: &Tree, table: &str, out: &mut String) {
+        if t.attributes.is_empty() {
+            return;
+        }
+        let heading = if table.is_empty() { "attributes".into() } else { format!("{}.attributes", table) };
+        writeln!(out, "\n[{}]", heading).unwrap();
+        for (name, value) in &t.attributes {
+            writeln!(out, "{} = {}", json::quote(name), json::quote(value)).unwrap();
+        }
+    }
+
+    let mut out = String::new();
+    write_fields(tree, &mut out);
+    write_attributes(tree, "", &mut out);
+    write_children(tree, "children", &mut out);
+    out
+}
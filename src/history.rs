@@ -0,0 +1,183 @@
+//! A log of every pick, kept in the data directory next to the pick trees.
+//!
+//! The log has one line per pick, with tab-separated fields: the time of the pick
+//! (seconds since the Unix epoch), the tree identifier and then every key in the
+//! picked path. Tabs and backslashes in those are escaped as `\t` and `\\`.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Name of the history file inside the data directory. It's hidden so it's not
+/// mistaken for a pick tree.
+pub const HISTORY_FILE: &str = ".history";
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// A single pick
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Seconds since the Unix epoch
+    pub time: u64,
+    pub tree_id: String,
+    /// Keys from the root's child down to the picked leaf
+    pub path: Vec<String>,
+}
+
+impl Entry {
+    /// An entry for a pick made right now
+    pub fn now(tree_id: &str, path: &[String]) -> Self {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self { time, tree_id: tree_id.into(), path: path.to_vec() }
+    }
+
+    fn to_line(&self) -> String {
+        let mut line = self.time.to_string();
+        for field in std::iter::once(&self.tree_id).chain(&self.path) {
+            line.push('\t');
+            line.push_str(&escape(field));
+        }
+        line
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let time = fields.next()?.parse().ok()?;
+        let tree_id = unescape(fields.next()?);
+        let path = fields.map(unescape).collect();
+        Some(Self { time, tree_id, path })
+    }
+}
+
+/// Which entries to keep when reading the history
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    pub tree_id: Option<String>,
+    /// Only entries at or after this time
+    pub since: Option<u64>,
+    /// Only entries before this time
+    pub until: Option<u64>,
+}
+
+impl Filter {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        self.tree_id.as_ref().is_none_or(|id| *id == entry.tree_id)
+            && self.since.is_none_or(|t| entry.time >= t)
+            && self.until.is_none_or(|t| entry.time < t)
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\t', "\\t")
+}
+
+fn unescape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('t')) => { res.push('\t'); chars.next(); }
+            ('\\', Some('\\')) => { res.push('\\'); chars.next(); }
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
+/// Path to the history file in the data directory `dir`
+pub fn history_path<P: AsRef<Path>>(dir: P) -> PathBuf {
+    dir.as_ref().join(HISTORY_FILE)
+}
+
+/// Appends `entry` to the history in `dir`
+pub fn append<P: AsRef<Path>>(dir: P, entry: &Entry) -> io::Result<()> {
+    fs::create_dir_all(dir.as_ref())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path(dir))?;
+    writeln!(file, "{}", entry.to_line())
+}
+
+/// Reads the entries in the history of `dir` that match `filter`, oldest first.
+/// Lines that can't be understood are skipped.
+pub fn read<P: AsRef<Path>>(dir: P, filter: &Filter) -> io::Result<Vec<Entry>> {
+    let contents = match fs::read_to_string(history_path(dir)) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    Ok(contents.lines()
+        .filter_map(Entry::from_line)
+        .filter(|e| filter.matches(e))
+        .collect())
+}
+
+/// Paths picked from the tree `tree_id` in its latest `n` picks
+pub fn recent_paths<P: AsRef<Path>>(dir: P, tree_id: &str, n: usize) -> io::Result<Vec<Vec<String>>> {
+    let filter = Filter { tree_id: Some(tree_id.into()), ..Filter::default() };
+    let entries = read(dir, &filter)?;
+    Ok(entries.into_iter()
+        .rev()
+        .take(n)
+        .map(|e| e.path)
+        .collect())
+}
+
+/// Parses a `YYYY-MM-DD` date, returning the time its day starts (UTC)
+pub fn parse_date(s: &str) -> Option<u64> {
+    let mut parts = s.splitn(3, '-');
+    let y: i64 = parts.next()?.parse().ok()?;
+    let m: u32 = parts.next()?.parse().ok()?;
+    let d: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+
+    let days = days_from_civil(y, m, d);
+    u64::try_from(days).ok().map(|days| days * SECONDS_PER_DAY)
+}
+
+/// The time the day after `time` starts, handy to make date ranges inclusive
+pub fn next_day(time: u64) -> u64 {
+    (time / SECONDS_PER_DAY + 1) * SECONDS_PER_DAY
+}
+
+/// Formats a time as `YYYY-MM-DD HH:MM` (UTC)
+pub fn format_time(time: u64) -> String {
+    let (y, m, d) = civil_from_days((time / SECONDS_PER_DAY) as i64);
+    let secs = time % SECONDS_PER_DAY;
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", y, m, d, secs / 3600, secs / 60 % 60)
+}
+
+// Conversions between days since the Unix epoch and civil dates, following
+// Howard Hinnant's `days_from_civil` and `civil_from_days` algorithms
+
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = m as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
@@ -0,0 +1,215 @@
+//! Importing pick trees from data formats written by other tools, in the shape
+//! described in [`crate::export`].
+//!
+//! JSON can be read in full, but only the parts of YAML and TOML that
+//! [`crate::export`] writes are understood, such as block mappings and arrays of
+//! tables.
+
+use std::{error::Error, fmt};
+
+use crate::{
+    json::{self, SyntaxError, Value},
+    toml, writer, yaml, Link, ParseOptions, Tree,
+};
+
+/// Formats a tree can be imported from
+pub const FORMATS: &[&str] = &["json", "yaml", "toml"];
+
+/// Everything that can go wrong while importing a tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportError {
+    /// The input isn't valid in its format
+    Syntax(SyntaxError),
+    /// A node at `path` (keys from the root's child down to the node) isn't in
+    /// the shape of a pick tree node
+    InvalidNode { path: Vec<String>, message: String },
+    /// The node at `path` can't be written in the pick tree file format and read
+    /// back as the same node, e.g. because its key is empty or spans several lines
+    Unrepresentable { path: Vec<String> },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = |path: &[String]| match path.is_empty() {
+            true => "the root".to_owned(),
+            false => format!("`{}`", path.join("/")),
+        };
+        match self {
+            ImportError::Syntax(e) => write!(f, "{}", e),
+            ImportError::InvalidNode { path, message } => write!(f, "invalid node at {}: {}", at(path), message),
+            ImportError::Unrepresentable { path } => {
+                write!(f, "the node at {} can't be written in a pick tree file", at(path))
+            }
+        }
+    }
+}
+
+impl Error for ImportError {}
+
+impl From<SyntaxError> for ImportError {
+    fn from(e: SyntaxError) -> Self {
+        ImportError::Syntax(e)
+    }
+}
+
+/// Imports a tree written in `format`, one of [`FORMATS`]
+pub fn import(s: &str, format: &str) -> Option<Result<Tree, ImportError>> {
+    match format {
+        "json" => Some(from_json(s)),
+        "yaml" => Some(from_yaml(s)),
+        "toml" => Some(from_toml(s)),
+        _ => None,
+    }
+}
+
+/// Reads a tree from JSON. The root's key may be left out.
+pub fn from_json(s: &str) -> Result<Tree, ImportError> {
+    from_value(&json::parse(s)?)
+}
+
+/// Reads a tree from YAML, in the shape written by [`crate::export::to_yaml`]
+pub fn from_yaml(s: &str) -> Result<Tree, ImportError> {
+    from_value(&yaml::parse(s)?)
+}
+
+/// Reads a tree from TOML, in the shape written by [`crate::export::to_toml`]
+pub fn from_toml(s: &str) -> Result<Tree, ImportError> {
+    from_value(&toml::parse(s)?)
+}
+
+fn from_value(value: &Value) -> Result<Tree, ImportError> {
+    let tree = node(value, None)?;
+    check_representable(&tree, &mut Vec::new())?;
+    Ok(tree)
+}
+
+/// Reads the node in `value`. `path` holds the keys of its ancestors, and is
+/// `None` for the root.
+fn node(value: &Value, path: Option<&[String]>) -> Result<Tree, ImportError> {
+    let members = match value {
+        Value::Object(members) => members,
+        // Errors in nodes that can't even hold a key are reported at their parent
+        other => {
+            let message = match path {
+                None => format!("expected an object, found {}", other.kind()),
+                Some(_) => format!("children must be objects, found {}", other.kind()),
+            };
+            return Err(ImportError::InvalidNode { path: path.unwrap_or_default().to_vec(), message });
+        }
+    };
+
+    let key = members.iter().find(|(name, _)| name == "key").map(|(_, value)| value);
+    let here: Vec<String> = match (path, key) {
+        (None, _) => Vec::new(),
+        (Some(path), Some(Value::String(key))) => path.iter().cloned().chain([key.clone()]).collect(),
+        (Some(path), _) => path.to_vec(),
+    };
+    let invalid = |message: String| ImportError::InvalidNode { path: here.clone(), message };
+
+    let mut tree = Tree::new(String::new());
+    let mut children = &[][..];
+    for (name, value) in members {
+        match (name.as_str(), value) {
+            ("key", Value::String(key)) => tree.key = key.clone(),
+            ("link", Value::String(target)) => {
+                tree.link = Some(Link { target: target.clone(), options: ParseOptions::default() });
+            }
+            ("weight", Value::Number(w)) if w.is_finite() && *w >= 0.0 => tree.weight = Some(*w),
+            ("weight", Value::Null) => tree.weight = None,
+            ("attributes", Value::Object(attributes)) => {
+                tree.attributes = attributes.iter()
+                    .map(|(name, value)| match value {
+                        Value::String(v) => Ok((name.clone(), v.clone())),
+                        other => Err(invalid(format!("attribute `{}` is {}, not a string", name, other.kind()))),
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            ("children", Value::Array(items)) => children = items,
+            ("key" | "link" | "attributes" | "children", other) => {
+                return Err(invalid(format!("`{}` can't be {}", name, other.kind())));
+            }
+            ("weight", _) => return Err(invalid("`weight` must be a non-negative number".into())),
+            _ => return Err(invalid(format!("unknown field `{}`", name))),
+        }
+    }
+    if key.is_none() && path.is_some() {
+        return Err(invalid("a child is missing its `key`".into()));
+    }
+
+    if tree.link.is_some() && !children.is_empty() {
+        return Err(invalid("links can't have children".into()));
+    }
+
+    tree.children = children.iter()
+        .map(|child| node(child, Some(&here)))
+        .collect::<Result<_, _>>()?;
+    Ok(tree)
+}
+
+/// Makes sure every node but the root survives being written and parsed again
+fn check_representable(tree: &Tree, path: &mut Vec<String>) -> Result<(), ImportError> {
+    for child in &tree.children {
+        path.push(child.key.clone());
+        if !writer::is_representable(child) {
+            return Err(ImportError::Unrepresentable { path: path.clone() });
+        }
+        check_representable(child, path)?;
+        path.pop();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export;
+
+    /// A tree with weights, attributes, a link, `#`s and keys that aren't ASCII
+    fn tree() -> Tree {
+        let mut pizza = Tree::new("pizza".into());
+        pizza.weight = Some(2.5);
+        pizza.attributes = vec![("cuisine".into(), "italian".into()), ("price".into(), "$$".into())];
+        pizza.children = vec![Tree::new("#1 margherita".into()), Tree::new("C# and F#".into())];
+
+        let mut linked = Tree::new("one".into());
+        linked.link = Some(Link { target: "other/one".into(), options: ParseOptions::default() });
+        linked.weight = Some(0.0);
+
+        let mut tree = Tree::new(String::new());
+        tree.children = vec![pizza, Tree::new("寿司 🍣".into()), Tree::new("say \"cheese\"".into()), linked];
+        tree
+    }
+
+    #[test]
+    fn json_round_trips() {
+        assert_eq!(from_json(&export::to_json(&tree())).unwrap(), tree());
+    }
+
+    #[test]
+    fn yaml_round_trips() {
+        assert_eq!(from_yaml(&export::to_yaml(&tree())).unwrap(), tree());
+    }
+
+    #[test]
+    fn toml_round_trips() {
+        assert_eq!(from_toml(&export::to_toml(&tree())).unwrap(), tree());
+    }
+
+    #[test]
+    fn nodes_must_be_in_shape() {
+        let error = from_json(r#"{"children": [{"key": "a", "weight": -1, "children": []}]}"#).unwrap_err();
+        assert!(matches!(&error, ImportError::InvalidNode { path, .. } if path == &["a"]), "{:?}", error);
+
+        let error = from_json(r#"{"children": [{"key": "a", "children": [3]}]}"#).unwrap_err();
+        assert!(matches!(&error, ImportError::InvalidNode { path, .. } if path == &["a"]), "{:?}", error);
+    }
+
+    #[test]
+    fn nodes_must_be_representable() {
+        for key in ["", "Salad {vegan}", "-> x", "two\nlines"] {
+            let json = format!(r#"{{"children": [{{"key": "a", "children": [{{"key": {}}}]}}]}}"#, json::quote(key));
+            let error = from_json(&json).unwrap_err();
+            assert!(matches!(&error, ImportError::Unrepresentable { path } if path[0] == "a"), "{:?}", error);
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! Just enough JSON to read and write pick trees.
+
+use std::{error::Error, fmt, fmt::Write};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// Members are kept in the order they were written
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Short description of the kind of value, for error messages
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "a boolean",
+            Value::Number(_) => "a number",
+            Value::String(_) => "a string",
+            Value::Array(_) => "an array",
+            Value::Object(_) => "an object",
+        }
+    }
+}
+
+/// JSON, or YAML or TOML, that couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub message: String,
+    /// 1-based
+    pub line: usize,
+    /// 1-based, in characters
+    pub column: usize,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message, self.line, self.column)
+    }
+}
+
+impl Error for SyntaxError {}
+
+impl SyntaxError {
+    /// Moves an error found in a snippet of a document to where the snippet is in
+    /// the document: line `line`, starting at column `column`
+    pub fn at(self, line: usize, column: usize) -> Self {
+        let column = if self.line == 1 { self.column + column - 1 } else { self.column };
+        SyntaxError { line: self.line + line - 1, column, ..self }
+    }
+}
+
+/// Writes `s` as a quoted string. The escapes used are also valid in YAML and
+/// TOML double-quoted strings.
+pub fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => write!(quoted, "\\u{:04X}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Writes a number without a fractional part when it has none
+pub fn number(n: f64) -> String {
+    format!("{}", n)
+}
+
+/// Parses a JSON document
+pub fn parse(s: &str) -> Result<Value, SyntaxError> {
+    let mut parser = Parser { chars: s.chars().collect(), pos: 0 };
+    let value = parser.value()?;
+    parser.whitespace();
+    match parser.peek() {
+        None => Ok(value),
+        Some(_) => Err(parser.error("unexpected trailing characters")),
+    }
+}
+
+/// Parses the JSON value at the start of `s`, and returns it with the rest of `s`
+pub fn parse_prefix(s: &str) -> Result<(Value, &str), SyntaxError> {
+    let mut parser = Parser { chars: s.chars().collect(), pos: 0 };
+    let value = parser.value()?;
+    let rest = s.char_indices().nth(parser.pos).map_or("", |(i, _)| &s[i..]);
+    Ok((value, rest))
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> SyntaxError {
+        let before = &self.chars[..self.pos.min(self.chars.len())];
+        let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+        let column = before.iter().rev().take_while(|&&c| c != '\n').count() + 1;
+        SyntaxError { message: message.into(), line, column }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn whitespace(&mut self) {
+        while self.peek().is_some_and(|c| matches!(c, ' ' | '\t' | '\n' | '\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SyntaxError> {
+        self.whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", expected)))
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Value) -> Result<Value, SyntaxError> {
+        let end = self.pos + word.chars().count();
+        if self.chars.get(self.pos..end).is_some_and(|w| w.iter().copied().eq(word.chars())) {
+            self.pos = end;
+            Ok(value)
+        } else {
+            Err(self.error("expected a value"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, SyntaxError> {
+        self.whitespace();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Value::String),
+            Some('t') => self.literal("true", Value::Bool(true)),
+            Some('f') => self.literal("false", Value::Bool(false)),
+            Some('n') => self.literal("null", Value::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, SyntaxError> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a member name"));
+            }
+            let name = self.string()?;
+            self.expect(':')?;
+            members.push((name, self.value()?));
+
+            self.whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Object(members)),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("expected `,` or `}`"));
+                }
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, SyntaxError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(items)),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("expected `,` or `]`"));
+                }
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, SyntaxError> {
+        let digits: String = (0..4).filter_map(|_| self.next()).collect();
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .filter(|_| digits.len() == 4)
+            .ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn string(&mut self) -> Result<String, SyntaxError> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(s),
+                Some('\\') => match self.next() {
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some('/') => s.push('/'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('u') => {
+                        let mut code = self.hex4()?;
+                        // Surrogate pairs
+                        if (0xD800..0xDC00).contains(&code) && self.next() == Some('\\') && self.next() == Some('u') {
+                            let low = self.hex4()?;
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                        }
+                        s.push(char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))?);
+                    }
+                    _ => {
+                        self.pos -= 1;
+                        return Err(self.error("invalid escape"));
+                    }
+                },
+                Some(c) if c.is_control() => {
+                    self.pos -= 1;
+                    return Err(self.error("control character in string"));
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, SyntaxError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse().map(Value::Number).map_err(|_| {
+            self.pos = start;
+            self.error("invalid number")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(s: &str) -> (String, usize, usize) {
+        let e = parse(s).unwrap_err();
+        (e.message, e.line, e.column)
+    }
+
+    #[test]
+    fn parses_values() {
+        let value = parse(r#"{"a": [1, -2.5e1, true, null], "b": "\"\u00e9\ud83c\udf55\n", "c": {}}"#).unwrap();
+        assert_eq!(value, Value::Object(vec![
+            ("a".into(), Value::Array(vec![Value::Number(1.0), Value::Number(-25.0), Value::Bool(true), Value::Null])),
+            ("b".into(), Value::String("\"é🍕\n".into())),
+            ("c".into(), Value::Object(Vec::new())),
+        ]));
+    }
+
+    #[test]
+    fn quoted_strings_parse_back() {
+        let s = "tab\t \"quote\" back\\slash \u{7} ünïcödé";
+        assert_eq!(parse(&quote(s)).unwrap(), Value::String(s.into()));
+    }
+
+    #[test]
+    fn errors_tell_where_they_are() {
+        assert_eq!(error("{\n  \"a\": 1,\n  \"b\" 2\n}"), ("expected `:`".into(), 3, 7));
+        assert_eq!(error("[1, 2\n"), ("expected `,` or `]`".into(), 2, 1));
+        assert_eq!(error("{\"a\": \"b\n"), ("control character in string".into(), 1, 9));
+        assert_eq!(error("[1] x"), ("unexpected trailing characters".into(), 1, 5));
+    }
+}
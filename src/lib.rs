@@ -0,0 +1,49 @@
+//! wtp - What To Pick?
+//!
+//! Decision trees to help humans decide stuff. This crate holds everything the
+//! `wtp` binary is built upon, so pick trees can be embedded in other tools:
+//!
+//! - [`Tree`] is the decision tree itself
+//! - [`parser`] reads trees from the indentation-based file format, and
+//!   [`writer`] writes them back
+//! - [`picker`] walks down a tree to pick one of its leaves
+//! - [`export`] turns trees into diagrams and other data formats, and
+//!   [`import`] reads them back
+//! - [`questions`] generates trees out of options tagged with attributes
+//! - [`search`] jumps straight to an option with a fuzzy search
+//! - [`build`] makes a tree out of a flat list of options
+//! - [`duel`] ranks the options of a tree by comparing them two at a time
+//! - [`storage`] knows where pick trees are kept
+//! - [`history`] remembers what was picked
+//!
+//! ```no_run
+//! use wtp::{picker::{self, InteractivePicker}, storage, Tree};
+//!
+//! let dirs = storage::SearchPath::from_env();
+//! let file = dirs.find("food/lunch").unwrap().expect("there's no lunch tree");
+//! let tree = Tree::from_file(&file, &dirs).expect("couldn't parse the lunch tree");
+//! if let Ok(Some(path)) = picker::pick(&tree, &mut InteractivePicker) {
+//!     println!("{}", path.join("/"));
+//! }
+//! ```
+
+pub mod build;
+pub mod duel;
+pub mod export;
+pub mod history;
+pub mod import;
+mod json;
+pub mod parser;
+pub mod picker;
+pub mod questions;
+mod rng;
+pub mod search;
+pub mod storage;
+mod toml;
+pub mod tree;
+pub mod writer;
+mod yaml;
+
+pub use parser::{ParseError, ParseOptions};
+pub use picker::Picker;
+pub use tree::{Link, LinkError, ResolveError, Tree};
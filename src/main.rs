@@ -1,47 +1,28 @@
+mod cli;
+mod completions;
+
 use std::{
     env,
-    collections::HashSet,
     ffi::{OsStr, OsString},
-    path::Path,
-    fs::{self, File},
-    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    fs,
+    io::{self, IsTerminal},
+    num::NonZeroUsize,
     process,
     error::Error,
 };
 
-use inquire::Select;
-
-const HELP_MESSAGE: &'static str = concat!("
-wtp - What To Pick?
-Decision trees to help humans decide stuff
-v", env!("CARGO_PKG_VERSION"), "
-
-USAGE:
-    wtp [FLAG] PICK_TREE_ID
-
-FLAG:
-    -h, --help  Shows this message
-    -e, --edit  Edits the PICK_TREE_ID file
-    -f, --file  Outputs the path for the PICK_TREE_ID file
-    -l, --list  Lists the pick trees you've created
-    no flag     Interactively helps you pick one of the options from a selected tree
-
-PICK_TREE file format:
-    It's a tree where siblings are in the same indentation level and children
-    have more indentation than their parents. Example:
-
-    A node in level 1
-        Some child in level 2
-        Another child in level 2
-    Another node in level 1
-        A child of the node above
-        Another child of that same node
-        Yet another child of that node
-            A node in level 3
-            Another one in level 3
-
-    TODO: explain how it works better. I'll do it later. Probably...
-");
+use cli::{Matches, Parsed};
+use inquire::error::InquireError;
+use wtp::{
+    build::{self, InteractiveGrouper},
+    duel::{self, InteractiveJudge},
+    export, history, import, parser,
+    picker::{self, InteractivePicker, PickError, RandomPicker},
+    questions, search,
+    storage::{self, SearchPath},
+    writer, ParseError, ParseOptions, Picker, Tree,
+};
 
 fn nonempty_env_var<K: AsRef<OsStr>>(k: K) -> Option<String> {
     env::var(k).ok().filter(|x| !x.is_empty())
@@ -64,126 +45,496 @@ fn spawn_editor(file: &Path) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn list_files<P: AsRef<Path>>(path: P) -> Result<(), Box<dyn Error>> {
-    fs::read_dir(path)?
-        .map(|entry| entry.unwrap())
-        .filter(|entry| entry.path().is_file())
-        .map(|entry| println!("{}", entry.file_name().to_string_lossy()))
-        .count();
-    Ok(())
+/// Explains to the user why the tree in `file` couldn't be parsed
+fn report_parse_error(file: &Path, id: &str, e: &ParseError) {
+    match e {
+        ParseError::IoError(io) if io.kind() == io::ErrorKind::NotFound => {
+            eprintln!("There's no pick tree called `{}` yet!", id);
+            eprintln!("Create it with `wtp new {}` or see `wtp help` for more options.", id);
+        }
+        _ => eprintln!("Couldn't read the pick tree <{}>: {}", file.to_string_lossy(), e),
+    }
 }
 
-/// Reads the env::args and returns a pair (set of flags, pick tree identifier)
-fn args() -> (HashSet<String>, Option<String>) {
-    let mut flags = HashSet::new();
-    let mut id = None;
-    for arg in env::args().skip(1) {
-        if arg.starts_with("-") {
-            flags.insert(arg);
-        } else {
-            id = Some(arg);
+/// Where new trees and the pick history go
+fn home(dirs: &SearchPath) -> Result<&Path, Box<dyn Error>> {
+    dirs.home.as_deref().ok_or_else(|| {
+        format!(
+            "Couldn't find your home directory to keep pick trees in. Choose a directory with --dir or ${}.",
+            storage::DIR_VAR
+        ).into()
+    })
+}
+
+/// The file of the tree identified by `id`: the first one in the search path, or
+/// else where it would be created
+fn tree_file(dirs: &SearchPath, id: &str) -> Result<PathBuf, Box<dyn Error>> {
+    match dirs.find(id)? {
+        Some(file) => Ok(file),
+        None => Ok(storage::tree_path(home(dirs)?, id)?),
+    }
+}
+
+/// Reads the tree identified by `id`, or exits explaining why it couldn't
+fn read_tree(dirs: &SearchPath, id: &str, m: &Matches) -> Result<Tree, Box<dyn Error>> {
+    let options = parse_options(dirs, m)?;
+    let file = dirs.find(id)?.unwrap_or_else(|| {
+        eprintln!("There's no pick tree called `{}` yet!", id);
+        eprintln!("Create it with `wtp new {}` or see `wtp help` for more options.", id);
+        process::exit(1);
+    });
+    Ok(Tree::from_file_with(&file, &options).unwrap_or_else(|e| {
+        report_parse_error(&file, id, &e);
+        process::exit(1);
+    }))
+}
+
+/// Builds the parser options out of the `--strict` and `--tab-width` options.
+/// Included and linked trees are looked up like any other tree.
+fn parse_options(dirs: &SearchPath, m: &Matches) -> Result<ParseOptions, Box<dyn Error>> {
+    let options = if m.flag("strict") {
+        ParseOptions::strict()
+    } else {
+        ParseOptions::default()
+    };
+    let mut options = options.with_include_dirs(dirs.dirs.clone());
+
+    if let Some(width) = m.parsed::<NonZeroUsize>("tab-width", "a positive number")? {
+        options = options.with_tab_width(width.get());
+    }
+    Ok(options)
+}
+
+/// The picker to use according to the `--random` and `--seed` options
+fn picker(m: &Matches) -> Result<Box<dyn Picker>, Box<dyn Error>> {
+    if !m.flag("random") {
+        if m.value("seed").is_some() {
+            return Err(cli::UsageError {
+                message: "--seed only applies to --random".into(),
+                command: Some(m.command),
+            }.into());
         }
+        return Ok(Box::new(InteractivePicker));
+    }
+
+    match m.parsed("seed", "a non-negative number")? {
+        Some(seed) => Ok(Box::new(RandomPicker::with_seed(seed))),
+        None => Ok(Box::new(RandomPicker::new())),
     }
-    (flags, id)
 }
 
-struct Tree {
-    key: String,
-    children: Vec<Tree>,
+/// Parses the `YYYY-MM-DD` date in a `--since` or `--until` option
+fn date_option(m: &Matches, long: &str) -> Result<Option<u64>, Box<dyn Error>> {
+    m.value(long)
+        .map(|date| history::parse_date(date)
+            .ok_or_else(|| format!("--{} expects a date like 2022-03-14, got `{}`", long, date).into()))
+        .transpose()
+}
+
+/// Removes from `tree` the options picked recently, according to the `--fresh`
+/// option. If every option was picked recently, `tree` is kept whole.
+fn freshen(mut tree: Tree, dirs: &SearchPath, id: &str, m: &Matches) -> Result<Tree, Box<dyn Error>> {
+    let n = match m.parsed("fresh", "a non-negative number")? {
+        Some(n) => n,
+        None => return Ok(tree),
+    };
+
+    let recent = history::recent_paths(home(dirs)?, id, n)?;
+    for path in &recent {
+        tree.follow_links(path)?;
+    }
+    let fresh = tree.without_leaves(&recent);
+    if fresh.is_leaf() {
+        eprintln!("Everything in `{}` was picked recently, so all options are available.", id);
+        Ok(tree)
+    } else {
+        Ok(fresh)
+    }
 }
 
-impl Tree {
-    pub fn new(key: String) -> Self {
-        Self { key, children: Vec::new() }
+/// Picks a single leaf of `start`, the node at the end of `path`, following the
+/// `--search` and `--random` options. Returns the path to the leaf.
+fn pick_once(
+    mut path: Vec<String>,
+    mut start: &Tree,
+    m: &Matches,
+    picker: &mut dyn Picker,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let prompts = (m.flag("search") && !start.is_leaf())
+        || (!m.flag("random") && picker::needs_choice(start));
+    if prompts && !io::stdin().is_terminal() {
+        return Err(InquireError::NotTTY.into());
     }
 
-    pub fn from_file(file: &Path) -> Self {
-        let file = File::open(file).expect(&format!("Couldn't open file <{}>", file.to_string_lossy()));
-        let reader = BufReader::new(file);
+    if m.flag("search") && !start.is_leaf() {
+        let (subpath, node) = search::prompt(start)?;
+        path.extend(subpath);
+        start = node;
+    }
 
-        // Start a stack of parent nodes
-        // Every item in the stack is a pair (node, indentation level)
-        let mut parents = vec![ (Tree::new("".into()), -1) ];
-        for line in reader.lines() {
-            let line = line.unwrap();
-            let line = line.as_str();
+    // Prompt errors are kept as they are, for `main` to tell how the pick ended
+    let picked = picker::pick(start, picker).map_err(|e| -> Box<dyn Error> {
+        match e {
+            PickError::Prompt(e) => e.into(),
+            PickError::Link(e) => e.into(),
+        }
+    })?;
+    path.extend(picked.unwrap_or_default());
+    Ok(path)
+}
 
-            // count whitespace characters before
-            let ws = line.chars().take_while(|c| c.is_whitespace()).count();
+/// Decides what to pick, interactively or at random
+fn cmd_pick(dirs: &SearchPath, m: &Matches) -> Result<(), Box<dyn Error>> {
+    let id = m.arg(0).unwrap_or(storage::DEFAULT_TREE_ID);
+    let tree = questions::generate(&read_tree(dirs, id, m)?);
+    let mut tree = freshen(tree, dirs, id, m)?;
+    if tree.is_leaf() {
+        eprintln!("Nothing to pick from! Add some options with `wtp edit {}`.", id);
+        return Ok(());
+    }
 
-            if !line[ws..].is_empty() {
-                let node = Tree::new(line[ws..].into());
+    // --path may go through links
+    let path = m.value("path").unwrap_or_default();
+    let steps: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    tree.follow_links(&steps)?;
+    let (prefix, _) = tree.resolve(path)?;
+    let count = m.parsed("count", "a positive number")?.map_or(1, NonZeroUsize::get);
+    let mut picker = picker(m)?;
+    let mut picked: Vec<Vec<String>> = Vec::new();
+    while picked.len() < count {
+        // Options can't be picked twice
+        let remaining = tree.without_leaves(&picked);
+        let start = match remaining.get(&prefix) {
+            Some(start) if !remaining.is_leaf() => start,
+            // Everything under --path was already picked
+            _ => break,
+        };
 
-                // Remove nodes that aren't ancestors of `node` and append them
-                // to their parents
-                while ws as i32 <= parents.last().unwrap().1 {
-                    let (u, _ws) = parents.pop().unwrap();
-                    parents.last_mut().unwrap().0.children.push(u);
-                }
+        let path = pick_once(prefix.clone(), start, m, picker.as_mut())?;
 
-                // Push current node to the stack
-                parents.push((node, ws as i32));
-            }
+        let saved = home(dirs).and_then(|home| Ok(history::append(home, &history::Entry::now(id, &path))?));
+        if let Err(e) = saved {
+            eprintln!("Couldn't save this pick to the history: {}", e);
         }
 
-        // Append last nodes to their parents
-        while parents.len() >= 2 {
-            let (u, _ws) = parents.pop().unwrap();
-            parents.last_mut().unwrap().0.children.push(u);
+        if m.flag("full-path") {
+            println!("{}", path.join("/"));
+        } else {
+            println!("{}", path.last().unwrap());
         }
+        // Links stay followed, so what was picked through them can be removed
+        tree.follow_links(&path)?;
+        picked.push(path);
+    }
 
-        parents.pop().unwrap().0
+    match picked.len() {
+        n if n >= count => {}
+        1 => eprintln!("There was only 1 option to pick from."),
+        n => eprintln!("There were only {} options to pick from.", n),
     }
+    Ok(())
 }
 
-fn pick(tree: &Tree) {
-    if tree.children.is_empty() {
-        println!("Nothing to pick from! See `wtp --help` for more options.");
-        return;
+/// Builds a tree out of a flat list of options
+fn cmd_build(dirs: &SearchPath, m: &Matches) -> Result<(), Box<dyn Error>> {
+    let id = m.arg(0).unwrap();
+    let file = tree_file(dirs, id)?;
+    if file.exists() && !m.flag("force") {
+        return Err(format!("There's already a pick tree called `{}`. Use --force to overwrite it.", id).into());
     }
 
-    let mut t = tree;
-    while !t.children.is_empty() {
-        let options = t.children.iter().map(|n| &n.key).collect();
-        let select = Select::new("", options)
-            .with_vim_mode(true);
-        let res = select.raw_prompt().unwrap();
+    // When the list is piped in, the prompts read from the terminal directly
+    let list = match m.arg(1) {
+        Some(path) if path != "-" => fs::read_to_string(path)?,
+        _ => io::read_to_string(io::stdin())?,
+    };
 
-        t = &t.children[res.index];
+    let options = build::read_options(&list);
+    // Options are written as they are, so they can't look like anything else
+    if let Some(option) = options.iter().find(|o| !writer::is_representable(&Tree::new(o.to_string()))) {
+        return Err(format!(
+            "The option `{}` would be read as a weight, attributes, a link or an include in a pick tree.",
+            option
+        ).into());
     }
+
+    let tree = build::build(options, &mut InteractiveGrouper)?;
+    if tree.is_leaf() {
+        return Err("There are no options in the list.".into());
+    }
+
+    fs::create_dir_all(file.parent().unwrap())?;
+    fs::write(&file, writer::to_string(&tree))?;
+    eprintln!("Saved the pick tree as `{}`.", id);
+    Ok(())
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let (flags, tree_id) = args();
+/// Ranks the options of a tree with a series of duels
+fn cmd_duel(dirs: &SearchPath, m: &Matches) -> Result<(), Box<dyn Error>> {
+    let id = m.arg(0).unwrap_or(storage::DEFAULT_TREE_ID);
+    let tree = read_tree(dirs, id, m)?;
 
-    let dir = directories::BaseDirs::new().unwrap()
-        .data_dir().join("WhatToPick");
+    let leaves = duel::leaves(&tree)?;
+    if leaves.len() >= 2 && !io::stdin().is_terminal() {
+        return Err(InquireError::NotTTY.into());
+    }
+    let ranking = duel::rank(leaves, &mut InteractiveJudge::default())?;
 
-    let file = dir.join(tree_id.unwrap_or("default".into()));
+    for path in &ranking {
+        if m.flag("full-path") {
+            println!("{}", path.join("/"));
+        } else {
+            println!("{}", path.last().unwrap());
+        }
+    }
 
-    // Print help message
-    if flags.contains("--help") || flags.contains("-h") {
-        println!("{}", HELP_MESSAGE);
+    if let Some(save_id) = m.value("save") {
+        let save_file = tree_file(dirs, save_id)?;
+        fs::create_dir_all(save_file.parent().unwrap())?;
+        fs::write(&save_file, writer::to_string(&duel::reorder(&tree, &ranking)))?;
+        eprintln!("Saved the ranking as `{}`.", save_id);
     }
-    // Create directory and open editor to edit the file
-    else if flags.contains("--edit") || flags.contains("-e") {
-        fs::create_dir_all(&dir)
-            .expect(&format!("Unable to create directory <{}>", dir.to_string_lossy()));
-        spawn_editor(file.as_path())?;
+    Ok(())
+}
+
+/// Prints a tree in another format
+fn cmd_export(dirs: &SearchPath, m: &Matches) -> Result<(), Box<dyn Error>> {
+    let id = m.arg(0).unwrap_or(storage::DEFAULT_TREE_ID);
+    let tree = read_tree(dirs, id, m)?;
+
+    let format = m.value("format").unwrap_or("dot");
+    let exported = export::export(&tree, id, format).ok_or_else(|| cli::UsageError {
+        message: format!("unknown format `{}`, expected one of: {}", format, export::FORMATS.join(", ")),
+        command: Some(m.command),
+    })?;
+    print!("{}", exported);
+    Ok(())
+}
+
+/// Saves a tree read from another format
+fn cmd_import(dirs: &SearchPath, m: &Matches) -> Result<(), Box<dyn Error>> {
+    let id = m.arg(0).unwrap();
+    let file = tree_file(dirs, id)?;
+    if file.exists() && !m.flag("force") {
+        return Err(format!("There's already a pick tree called `{}`. Use --force to overwrite it.", id).into());
     }
-    // Print the file path
-    else if flags.contains("--file") || flags.contains("-f") {
-        println!("{}", file.to_string_lossy());
+
+    let input = match m.arg(1) {
+        Some(path) if path != "-" => fs::read_to_string(path)?,
+        _ => io::read_to_string(io::stdin())?,
+    };
+
+    let format = m.value("format").unwrap_or("json");
+    let tree = import::import(&input, format).ok_or_else(|| cli::UsageError {
+        message: format!("unknown format `{}`, expected one of: {}", format, import::FORMATS.join(", ")),
+        command: Some(m.command),
+    })??;
+    if tree.is_leaf() {
+        return Err("There are no options to import.".into());
+    }
+
+    fs::create_dir_all(file.parent().unwrap())?;
+    fs::write(&file, writer::to_string(&tree))?;
+    eprintln!("Saved the pick tree as `{}`.", id);
+    Ok(())
+}
+
+/// Rewrites a tree in the canonical style, or checks whether it already is
+fn cmd_fmt(dirs: &SearchPath, m: &Matches) -> Result<(), Box<dyn Error>> {
+    let id = m.arg(0).unwrap_or(storage::DEFAULT_TREE_ID);
+    let file = tree_file(dirs, id)?;
+    let options = parse_options(dirs, m)?;
+    let read = |file: &Path| -> Result<(String, String), ParseError> {
+        let text = String::from_utf8(fs::read(file)?).map_err(|e| {
+            let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+            ParseError::InvalidUtf8 { line: valid.iter().filter(|&&b| b == b'\n').count() + 1 }
+        })?;
+        let formatted = writer::format(&text, &options)?;
+        Ok((text, formatted))
+    };
+    let (text, formatted) = read(&file).unwrap_or_else(|e| {
+        report_parse_error(&file, id, &e);
+        process::exit(1);
+    });
+
+    // Never rewrite a tree into a different one
+    let parsed = |s: &str| parser::parse_with(s.as_bytes(), &options).ok();
+    if parsed(&formatted) != parsed(&text) {
+        return Err(format!("Formatting `{}` would change the tree, so it was left as it is. This is a bug!", id).into());
     }
-    // Lists the trees you've created
-    else if flags.contains("--list") || flags.contains("-l") {
-        list_files(&dir)?;
+
+    if formatted == text {
+        return Ok(());
+    }
+    if m.flag("check") {
+        return Err(format!("`{}` isn't formatted. Run `wtp fmt {}` to format it.", id, id).into());
     }
-    // Interactively decide what to pick
-    else {
-        let tree = Tree::from_file(file.as_path());
-        pick(&tree);
+    fs::write(&file, formatted)?;
+    eprintln!("Formatted `{}`.", id);
+    Ok(())
+}
+
+/// Creates the data directory and opens the editor to edit a tree
+fn cmd_edit(dirs: &SearchPath, m: &Matches) -> Result<(), Box<dyn Error>> {
+    let file = tree_file(dirs, m.arg(0).unwrap_or(storage::DEFAULT_TREE_ID))?;
+    fs::create_dir_all(file.parent().unwrap())?;
+    spawn_editor(file.as_path())
+}
+
+/// Creates an empty tree and opens the editor to fill it in
+fn cmd_new(dirs: &SearchPath, m: &Matches) -> Result<(), Box<dyn Error>> {
+    let id = m.arg(0).unwrap();
+    let file = tree_file(dirs, id)?;
+    if file.exists() {
+        return Err(format!("There's already a pick tree called `{}`. Edit it with `wtp edit {}`.", id, id).into());
     }
 
+    fs::create_dir_all(file.parent().unwrap())?;
+    fs::File::create(&file)?;
+    spawn_editor(file.as_path())
+}
+
+/// Deletes a tree
+fn cmd_rm(dirs: &SearchPath, m: &Matches) -> Result<(), Box<dyn Error>> {
+    let id = m.arg(0).unwrap();
+    let file = dirs.find(id)?.ok_or_else(|| format!("There's no pick tree called `{}`.", id))?;
+    fs::remove_file(&file)?;
+
+    // Namespaces left empty go away with their last tree
+    let dir = dirs.dirs.iter().find(|dir| file.starts_with(dir)).unwrap();
+    for namespace in file.ancestors().skip(1).take_while(|d| d != dir) {
+        if fs::remove_dir(namespace).is_err() {
+            break;
+        }
+    }
     Ok(())
 }
+
+/// Prints the path to the file of a tree
+fn cmd_path(dirs: &SearchPath, m: &Matches) -> Result<(), Box<dyn Error>> {
+    let file = tree_file(dirs, m.arg(0).unwrap_or(storage::DEFAULT_TREE_ID))?;
+    println!("{}", file.to_string_lossy());
+    Ok(())
+}
+
+/// Lists the trees you've created
+fn cmd_list(dirs: &SearchPath, m: &Matches) -> Result<(), Box<dyn Error>> {
+    let ids = dirs.list_trees()?;
+    if m.flag("flat") {
+        for id in ids {
+            println!("{}", id);
+        }
+        return Ok(());
+    }
+
+    // Namespaces are printed once, before the trees in them
+    let mut previous: Vec<&str> = Vec::new();
+    for id in &ids {
+        let (namespaces, name) = match id.rsplit_once('/') {
+            Some((namespaces, name)) => (namespaces.split('/').collect(), name),
+            None => (Vec::new(), id.as_str()),
+        };
+        let shared = previous.iter().zip(&namespaces).take_while(|(a, b)| a == b).count();
+        for (depth, namespace) in namespaces.iter().enumerate().skip(shared) {
+            println!("{}{}/", writer::INDENT.repeat(depth), namespace);
+        }
+        println!("{}{}", writer::INDENT.repeat(namespaces.len()), name);
+        previous = namespaces;
+    }
+    Ok(())
+}
+
+/// Prints the latest picks in the history
+fn cmd_history(dirs: &SearchPath, m: &Matches) -> Result<(), Box<dyn Error>> {
+    let filter = history::Filter {
+        tree_id: m.arg(0).map(String::from),
+        since: date_option(m, "since")?,
+        until: date_option(m, "until")?.map(history::next_day),
+    };
+    let limit = m.parsed("limit", "a non-negative number")?.unwrap_or(20);
+
+    let entries = history::read(home(dirs)?, &filter)?;
+    for entry in &entries[entries.len().saturating_sub(limit)..] {
+        println!("{}  {}  {}", history::format_time(entry.time), entry.tree_id, entry.path.join("/"));
+    }
+    Ok(())
+}
+
+/// Prints the completion script for a shell
+fn cmd_completions(_dirs: &SearchPath, m: &Matches) -> Result<(), Box<dyn Error>> {
+    let shell = m.arg(0).unwrap();
+    let script = completions::script(shell).ok_or_else(|| cli::UsageError {
+        message: format!("unsupported shell `{}`, expected one of: {}", shell, completions::SHELLS.join(", ")),
+        command: Some(m.command),
+    })?;
+    print!("{}", script);
+    Ok(())
+}
+
+fn run(m: &Matches) -> Result<(), Box<dyn Error>> {
+    let dirs = match m.value("dir") {
+        Some(dir) => SearchPath::with_dir(dir),
+        None => SearchPath::from_env(),
+    };
+    match m.command.name {
+        "pick" => cmd_pick(&dirs, m),
+        "build" => cmd_build(&dirs, m),
+        "duel" => cmd_duel(&dirs, m),
+        "edit" => cmd_edit(&dirs, m),
+        "export" => cmd_export(&dirs, m),
+        "import" => cmd_import(&dirs, m),
+        "fmt" => cmd_fmt(&dirs, m),
+        "new" => cmd_new(&dirs, m),
+        "rm" => cmd_rm(&dirs, m),
+        "path" => cmd_path(&dirs, m),
+        "list" => cmd_list(&dirs, m),
+        "history" => cmd_history(&dirs, m),
+        "completions" => cmd_completions(&dirs, m),
+        name => unreachable!("command `{}` isn't handled", name),
+    }
+}
+
+fn main() {
+    let matches = match cli::parse(env::args().skip(1)) {
+        Ok(Parsed::Run(matches)) => matches,
+        Ok(Parsed::Help(command)) => {
+            print!("{}", cli::help(command));
+            return;
+        }
+        Ok(Parsed::Version) => {
+            println!("wtp {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(&matches) {
+        let code = match e.downcast_ref::<InquireError>() {
+            Some(InquireError::OperationInterrupted) => {
+                eprintln!("Interrupted, nothing was picked.");
+                130
+            }
+            Some(InquireError::OperationCanceled) => {
+                eprintln!("Canceled, nothing was picked.");
+                1
+            }
+            Some(InquireError::NotTTY) if matches.command.name == "pick" => {
+                eprintln!("error: wtp needs a terminal to ask you what to pick.");
+                eprintln!("Use `--random` or `--path PATH` to pick without asking.");
+                1
+            }
+            Some(InquireError::NotTTY) => {
+                eprintln!("error: `wtp {}` needs a terminal to ask you questions.", matches.command.name);
+                1
+            }
+            _ => {
+                eprintln!("error: {}", e);
+                if e.is::<cli::UsageError>() { 2 } else { 1 }
+            }
+        };
+        process::exit(code);
+    }
+}
@@ -0,0 +1,593 @@
+//! Parser for the pick tree file format.
+//!
+//! It's a tree where siblings are in the same indentation level and children
+//! have more indentation than their parents:
+//!
+//! ```text
+//! even
+//!     2
+//!     4
+//! odd
+//!     1
+//!     3
+//! ```
+//!
+//! Blank lines are ignored, and so is everything after a `#` that starts a line
+//! or follows a whitespace, which allows for comments. A key that really needs a
+//! `#` there can escape it as `\#`:
+//!
+//! ```text
+//! # Where should we have lunch?
+//! pizza       # the one around the corner
+//! \#1 burgers
+//! ```
+//!
+//! A node may end with a weight annotation such as `pizza [w=3]`, which makes it
+//! three times as likely as its siblings to be picked at random. Weights must be
+//! non-negative numbers.
+//!
+//! Options can also be tagged with attributes, written between braces after the
+//! key and before the weight, such as `Sushi {cuisine: japanese, price: $$}`.
+//! See [`crate::questions`] for what they're used for.
+//!
+//! A line such as `@include restaurants` splices in the top-level nodes of
+//! another pick tree, found by its identifier in [`ParseOptions::include_dirs`],
+//! as if they were written in its place. An `@include` can't have children, and
+//! trees can't include themselves, directly or not.
+//!
+//! A line such as `-> movies/comedy` links to another pick tree, or to a node in
+//! it, which is only read when the link is picked. See [`Link`].
+//!
+//! By default, parsing is lenient: tabs and spaces may be mixed freely, and a tab
+//! advances the indentation to the next multiple of [`ParseOptions::tab_width`].
+//! In strict mode, a file must be indented either only with tabs or only with
+//! spaces, and anything else is reported as a [`ParseError::MixedIndentation`].
+//! In both modes, a node must line up with one of its ancestors' siblings when
+//! dedenting, otherwise parsing fails with
+//! [`ParseError::InconsistentIndentation`].
+
+use std::{
+    error::Error,
+    fmt,
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    storage::{self, InvalidId},
+    tree::{Attributes, Link},
+    Tree,
+};
+
+/// Everything that can go wrong while parsing a pick tree
+#[derive(Debug)]
+pub enum ParseError {
+    /// The tree couldn't be read at all, e.g. because the file doesn't exist
+    IoError(io::Error),
+    /// Line `line` (1-based) isn't valid UTF-8
+    InvalidUtf8 { line: usize },
+    /// The node in line `line` starts at column `column` (both 1-based), which is
+    /// neither deeper than the node above it nor aligned with any of its ancestors,
+    /// so it's unclear who its parent is
+    InconsistentIndentation { line: usize, column: usize },
+    /// In strict mode, the indentation of line `line` has a tab where spaces are
+    /// expected or vice versa, at column `column`
+    MixedIndentation { line: usize, column: usize },
+    /// The weight annotation in line `line` isn't a non-negative number
+    InvalidWeight { line: usize },
+    /// The attributes in line `line` aren't a list of `name: value` pairs
+    InvalidAttributes { line: usize },
+    /// Line `line` has annotations, like a weight, but nothing they annotate
+    EmptyKey { line: usize },
+    /// The `@include` in line `line` has no identifier, or has children
+    InvalidInclude { line: usize },
+    /// The link in line `line` has no target, or has children
+    InvalidLink { line: usize },
+    /// Line `line` includes `id`, but there's no pick tree called that
+    MissingInclude { line: usize, id: String },
+    /// Line `line` includes a tree by an identifier that can't name one, such
+    /// as `../x`
+    InvalidIncludeId { line: usize, error: InvalidId },
+    /// The trees in `ids` include each other in a cycle, which ends where it
+    /// starts
+    IncludeCycle { ids: Vec<String> },
+    /// The tree included as `id` couldn't be parsed
+    Included { id: String, error: Box<ParseError> },
+}
+
+/// How lenient the parser should be about indentation, and where included trees
+/// are found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Reject files that mix tabs and spaces for indentation
+    pub strict: bool,
+    /// How many columns a tab is worth in lenient mode
+    pub tab_width: usize,
+    /// Where the trees named by `@include` and links are looked up, in order.
+    /// When parsing a file without any, it's the directory of the file, which is
+    /// only right for trees outside namespaces: the includes of `food/lunch`
+    /// are looked up next to it, in `food`. Otherwise, `@include`s can't be
+    /// found.
+    pub include_dirs: Vec<PathBuf>,
+}
+
+impl ParseOptions {
+    pub const DEFAULT_TAB_WIDTH: usize = 4;
+
+    /// Options for strict mode
+    pub fn strict() -> Self {
+        Self { strict: true, ..Self::default() }
+    }
+
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width.max(1);
+        self
+    }
+
+    pub fn with_include_dirs(mut self, dirs: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.include_dirs = dirs.into_iter().collect();
+        self
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self { strict: false, tab_width: Self::DEFAULT_TAB_WIDTH, include_dirs: Vec::new() }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::IoError(e) => write!(f, "{}", e),
+            ParseError::InvalidUtf8 { line } => write!(f, "line {} is not valid UTF-8", line),
+            ParseError::InconsistentIndentation { line, column } => write!(
+                f,
+                "inconsistent indentation in line {}, column {}: it doesn't line up with any of the nodes above it",
+                line, column
+            ),
+            ParseError::MixedIndentation { line, column } => write!(
+                f,
+                "mixed tabs and spaces in the indentation of line {}, column {}",
+                line, column
+            ),
+            ParseError::InvalidWeight { line } => write!(
+                f,
+                "invalid weight in line {}: expected a non-negative number, like `[w=3]`",
+                line
+            ),
+            ParseError::InvalidAttributes { line } => write!(
+                f,
+                "invalid attributes in line {}: expected `name: value` pairs, like `{{cuisine: japanese, price: $$}}`",
+                line
+            ),
+            ParseError::EmptyKey { line } => write!(
+                f,
+                "line {} has a weight or attributes, but no option for them to go with",
+                line
+            ),
+            ParseError::InvalidInclude { line } => write!(
+                f,
+                "invalid `@include` in line {}: it needs the identifier of a pick tree, and can't have children",
+                line
+            ),
+            ParseError::InvalidLink { line } => write!(
+                f,
+                "invalid link in line {}: it needs a pick tree to link to, and can't have children",
+                line
+            ),
+            ParseError::MissingInclude { line, id } => write!(
+                f,
+                "line {} includes `{}`, but there's no pick tree called that",
+                line, id
+            ),
+            ParseError::InvalidIncludeId { line, error } => write!(f, "invalid `@include` in line {}: {}", line, error),
+            ParseError::IncludeCycle { ids } => write!(f, "trees include each other in a cycle: {}", ids.join(" -> ")),
+            ParseError::Included { id, error } => write!(f, "in the included tree `{}`: {}", id, error),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::IoError(e) => Some(e),
+            ParseError::InvalidIncludeId { error, .. } => Some(error),
+            ParseError::Included { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::IoError(e)
+    }
+}
+
+/// Parses the pick tree stored in `file`, which includes trees from its own
+/// directory. Trees in a [`crate::storage::SearchPath`] are better read with
+/// [`Tree::from_file`].
+pub fn parse_file(file: &Path) -> Result<Tree, ParseError> {
+    parse_file_with(file, &ParseOptions::default())
+}
+
+/// Parses the pick tree stored in `file` with the given options
+pub fn parse_file_with(file: &Path, options: &ParseOptions) -> Result<Tree, ParseError> {
+    let reader = BufReader::new(File::open(file)?);
+    let mut options = options.clone();
+    if options.include_dirs.is_empty() {
+        options.include_dirs.extend(file.parent().map(Path::to_path_buf));
+    }
+
+    // The file itself may be included somewhere down the line
+    let relative = options.include_dirs.iter().find_map(|dir| file.strip_prefix(dir).ok());
+    let id = relative.unwrap_or(file).to_string_lossy().replace(std::path::MAIN_SEPARATOR, "/");
+    let mut including = vec![(id, fs::canonicalize(file)?)];
+    parse_including(reader, &options, &mut including)
+}
+
+/// Parses a pick tree from a string
+pub fn parse_str(s: &str) -> Result<Tree, ParseError> {
+    parse(s.as_bytes())
+}
+
+/// Parses a pick tree from any buffered reader. The returned tree is rooted at a
+/// node with an empty key, whose children are the top-level nodes.
+pub fn parse<R: BufRead>(reader: R) -> Result<Tree, ParseError> {
+    parse_with(reader, &ParseOptions::default())
+}
+
+/// Measures the indentation of `line`. Returns a pair (indentation width,
+/// number of characters in the indentation).
+///
+/// `style` is the indentation character used by the file so far, which strict
+/// mode enforces on every line.
+pub(crate) fn indentation(
+    line: &str,
+    line_number: usize,
+    options: &ParseOptions,
+    style: &mut Option<char>,
+) -> Result<(usize, usize), ParseError> {
+    let mut width = 0;
+    let mut chars = 0;
+    for c in line.chars().take_while(|c| c.is_whitespace()) {
+        if options.strict {
+            match *style {
+                Some(s) if s != c => {
+                    return Err(ParseError::MixedIndentation { line: line_number, column: chars + 1 });
+                }
+                _ => *style = Some(c),
+            }
+        }
+
+        width = match c {
+            '\t' => (width / options.tab_width + 1) * options.tab_width,
+            _ => width + 1,
+        };
+        chars += 1;
+    }
+    Ok((width, chars))
+}
+
+/// Strips comments from the text of a line (after its indentation), unescaping
+/// any `\#` along the way. Returns an empty string for comment-only lines.
+pub(crate) fn strip_comments(text: &str) -> String {
+    let mut key = String::with_capacity(text.len());
+    let mut after_whitespace = true;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'#') => {
+                key.push('#');
+                chars.next();
+                after_whitespace = false;
+                continue;
+            }
+            '#' if after_whitespace => break,
+            _ => key.push(c),
+        }
+        after_whitespace = c.is_whitespace();
+    }
+
+    key.truncate(key.trim_end().len());
+    key
+}
+
+/// Splits a trailing weight annotation, like in `pizza [w=3]`, from a key
+fn split_weight(key: &str, line_number: usize) -> Result<(&str, Option<f64>), ParseError> {
+    let annotation = key.strip_suffix(']')
+        .and_then(|k| k.rsplit_once("[w="));
+
+    match annotation {
+        Some((key, weight)) => {
+            let weight = weight.trim().parse::<f64>()
+                .ok()
+                .filter(|w| w.is_finite() && *w >= 0.0)
+                .ok_or(ParseError::InvalidWeight { line: line_number })?;
+            Ok((key.trim_end(), Some(weight)))
+        }
+        None => Ok((key, None)),
+    }
+}
+
+/// Splits trailing attributes, like in `Sushi {cuisine: japanese}`, from a key
+fn split_attributes(key: &str, line_number: usize) -> Result<(&str, Attributes), ParseError> {
+    let annotation = key.strip_suffix('}')
+        .and_then(|k| k.rsplit_once('{'));
+
+    match annotation {
+        Some((key, attributes)) => {
+            let attributes = attributes.split(',')
+                .filter(|a| !a.trim().is_empty())
+                .map(|a| match a.split_once(':') {
+                    Some((name, value)) if !name.trim().is_empty() && !value.trim().is_empty() =>
+                        Ok((name.trim().into(), value.trim().into())),
+                    _ => Err(ParseError::InvalidAttributes { line: line_number }),
+                })
+                .collect::<Result<_, _>>()?;
+            Ok((key.trim_end(), attributes))
+        }
+        None => Ok((key, Vec::new())),
+    }
+}
+
+/// The identifier in an `@include` line, if it is one
+fn include_directive(key: &str) -> Option<&str> {
+    let id = key.strip_prefix("@include")?;
+    match id.chars().next() {
+        None => Some(""),
+        Some(c) if c.is_whitespace() => Some(id.trim()),
+        _ => None,
+    }
+}
+
+/// Parses the tree included as `id` in line `line_number`. `including` holds the
+/// identifiers and paths of the trees being parsed, from the outermost one.
+fn parse_include(
+    id: &str,
+    line_number: usize,
+    options: &ParseOptions,
+    including: &mut Vec<(String, PathBuf)>,
+) -> Result<Tree, ParseError> {
+    let file = storage::find_tree(&options.include_dirs, id)
+        .map_err(|error| ParseError::InvalidIncludeId { line: line_number, error })?
+        .ok_or_else(|| ParseError::MissingInclude { line: line_number, id: id.into() })?;
+    let file = fs::canonicalize(&file)
+        .map_err(|e| ParseError::Included { id: id.into(), error: Box::new(e.into()) })?;
+
+    if let Some(start) = including.iter().position(|(_, f)| *f == file) {
+        let mut ids: Vec<String> = including[start..].iter().map(|(id, _)| id.clone()).collect();
+        ids.push(id.into());
+        return Err(ParseError::IncludeCycle { ids });
+    }
+
+    including.push((id.into(), file.clone()));
+    let tree = File::open(&file)
+        .map_err(ParseError::from)
+        .and_then(|f| parse_including(BufReader::new(f), options, including));
+    including.pop();
+
+    tree.map_err(|e| match e {
+        // Cycles already tell which trees are involved
+        ParseError::IncludeCycle { .. } => e,
+        e => ParseError::Included { id: id.into(), error: Box::new(e) },
+    })
+}
+
+/// Parses a pick tree from any buffered reader with the given options
+pub fn parse_with<R: BufRead>(reader: R, options: &ParseOptions) -> Result<Tree, ParseError> {
+    parse_including(reader, options, &mut Vec::new())
+}
+
+fn parse_including<R: BufRead>(
+    mut reader: R,
+    options: &ParseOptions,
+    including: &mut Vec<(String, PathBuf)>,
+) -> Result<Tree, ParseError> {
+    let mut style = None;
+    // Indentation and error of the last `@include` or link, which can't have
+    // children, while no other node follows it
+    let mut childless: Option<(i32, ParseError)> = None;
+
+    // Start a stack of parent nodes
+    // Every item in the stack is a pair (node, indentation level)
+    let mut parents = vec![ (Tree::new("".into()), -1) ];
+    let mut buf = Vec::new();
+    for line_number in 1.. {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        let line = std::str::from_utf8(&buf)
+            .map_err(|_| ParseError::InvalidUtf8 { line: line_number })?;
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        let key = strip_comments(line.trim_start());
+
+        if !key.is_empty() {
+            let (ws, ws_chars) = indentation(line, line_number, options, &mut style)?;
+            let ws = ws as i32;
+
+            match childless.take() {
+                Some((parent_ws, e)) if ws > parent_ws => return Err(e),
+                _ => {}
+            }
+
+            let nodes = match include_directive(&key) {
+                Some("") => return Err(ParseError::InvalidInclude { line: line_number }),
+                Some(id) => {
+                    childless = Some((ws, ParseError::InvalidInclude { line: line_number }));
+                    parse_include(id, line_number, options, including)?.children
+                }
+                None => {
+                    let (key, weight) = split_weight(&key, line_number)?;
+                    let (key, attributes) = split_attributes(key, line_number)?;
+                    if key.is_empty() {
+                        return Err(ParseError::EmptyKey { line: line_number });
+                    }
+                    let mut node = match key.strip_prefix("->") {
+                        Some(target) => {
+                            let target = target.trim();
+                            let key = Link::key(target).ok_or(ParseError::InvalidLink { line: line_number })?;
+                            childless = Some((ws, ParseError::InvalidLink { line: line_number }));
+                            let mut node = Tree::new(key.into());
+                            node.link = Some(Link { target: target.into(), options: options.clone() });
+                            node
+                        }
+                        None => Tree::new(key.into()),
+                    };
+                    node.weight = weight;
+                    node.attributes = attributes;
+                    vec![node]
+                }
+            };
+
+            for node in nodes {
+                // Remove nodes that aren't ancestors of `node` and append them
+                // to their parents
+                while ws <= parents.last().unwrap().1 {
+                    let (u, u_ws) = parents.pop().unwrap();
+                    parents.last_mut().unwrap().0.children.push(u);
+
+                    // `node` would be a sibling of `u`, but they're not aligned
+                    if ws < u_ws && ws > parents.last().unwrap().1 {
+                        return Err(ParseError::InconsistentIndentation {
+                            line: line_number,
+                            column: ws_chars + 1,
+                        });
+                    }
+                }
+
+                // Push current node to the stack
+                parents.push((node, ws));
+            }
+        }
+    }
+
+    // Append last nodes to their parents
+    while parents.len() >= 2 {
+        let (u, _ws) = parents.pop().unwrap();
+        parents.last_mut().unwrap().0.children.push(u);
+    }
+
+    Ok(parents.pop().unwrap().0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    fn keys(tree: &Tree) -> Vec<&str> {
+        tree.children.iter().map(|c| c.key.as_str()).collect()
+    }
+
+    /// A fresh directory with the trees in `files`, as (identifier, text) pairs
+    fn trees(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("wtp-parser-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (id, text) in files {
+            fs::write(dir.join(id), text).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn strict_mode_rejects_mixed_indentation() {
+        let strict = ParseOptions::strict();
+        let error = parse_with("a\n\tb\n\t c\n".as_bytes(), &strict).unwrap_err();
+        assert!(matches!(error, ParseError::MixedIndentation { line: 3, column: 2 }), "{:?}", error);
+
+        let error = parse_with("a\n  b\nc\n\td\n".as_bytes(), &strict).unwrap_err();
+        assert!(matches!(error, ParseError::MixedIndentation { line: 4, column: 1 }), "{:?}", error);
+    }
+
+    #[test]
+    fn lenient_mode_measures_tabs_with_the_tab_width() {
+        let text = "a\n\tb\n    c\n";
+        let tree = parse_str(text).unwrap();
+        assert_eq!(keys(&tree.children[0]), ["b", "c"]);
+
+        let tree = parse_with(text.as_bytes(), &ParseOptions::default().with_tab_width(2)).unwrap();
+        assert_eq!(keys(&tree.children[0]), ["b"]);
+        assert_eq!(keys(&tree.children[0].children[0]), ["c"]);
+    }
+
+    #[test]
+    fn dedents_must_line_up_with_an_ancestor() {
+        let error = parse_str("a\n    b\n        c\n  d\n").unwrap_err();
+        assert!(matches!(error, ParseError::InconsistentIndentation { line: 4, column: 3 }), "{:?}", error);
+    }
+
+    #[test]
+    fn comments_are_skipped_unless_escaped() {
+        let tree = parse_str("# lunch\npizza  # the one around the corner\n    # nothing here\n\\#1 burgers\nC# and F#\n").unwrap();
+        assert_eq!(keys(&tree), ["pizza", "#1 burgers", "C# and F#"]);
+        assert!(tree.children[0].is_leaf());
+    }
+
+    #[test]
+    fn annotations_need_a_key() {
+        let error = parse_str("a\n[w=3]\n").unwrap_err();
+        assert!(matches!(error, ParseError::EmptyKey { line: 2 }), "{:?}", error);
+
+        let error = parse_str("a\n    {cuisine: thai}\n").unwrap_err();
+        assert!(matches!(error, ParseError::EmptyKey { line: 2 }), "{:?}", error);
+    }
+
+    #[test]
+    fn weights_must_be_non_negative_numbers() {
+        let tree = parse_str("a [w=2.5]\nb\n").unwrap();
+        assert_eq!(tree.children[0].weight, Some(2.5));
+        assert_eq!(tree.children[1].weight, None);
+
+        for text in ["a\nb [w=-1]\n", "a\nb [w=x]\n", "a\nb [w=inf]\n"] {
+            let error = parse_str(text).unwrap_err();
+            assert!(matches!(error, ParseError::InvalidWeight { line: 2 }), "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn includes_splice_the_included_tree() {
+        let dir = trees("splice", &[("inner", "b\n    c\nd\n")]);
+        let options = ParseOptions::default().with_include_dirs([dir.clone()]);
+        let tree = parse_with("a\n@include inner\ne\n".as_bytes(), &options).unwrap();
+        assert_eq!(keys(&tree), ["a", "b", "d", "e"]);
+        assert_eq!(keys(&tree.children[1]), ["c"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn includes_cant_have_children() {
+        let dir = trees("children", &[("inner", "b\n")]);
+        let options = ParseOptions::default().with_include_dirs([dir.clone()]);
+        let error = parse_with("a\n@include inner\n    c\n".as_bytes(), &options).unwrap_err();
+        assert!(matches!(error, ParseError::InvalidInclude { line: 2 }), "{:?}", error);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn includes_are_looked_up_by_valid_identifiers() {
+        let dir = trees("ids", &[]);
+        let options = ParseOptions::default().with_include_dirs([dir.clone()]);
+
+        let error = parse_with("a\n@include nowhere\n".as_bytes(), &options).unwrap_err();
+        assert!(matches!(&error, ParseError::MissingInclude { line: 2, id } if id == "nowhere"), "{:?}", error);
+
+        let error = parse_with("a\n@include ../x\n".as_bytes(), &options).unwrap_err();
+        assert!(matches!(&error, ParseError::InvalidIncludeId { line: 2, error } if error.id == "../x"), "{:?}", error);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn include_cycles_are_rejected() {
+        let dir = trees("cycle", &[("a", "x\n@include b\n"), ("b", "y\n@include a\n")]);
+        let error = parse_file(&dir.join("a")).unwrap_err();
+        assert!(matches!(&error, ParseError::IncludeCycle { ids } if ids == &["a", "b", "a"]), "{:?}", error);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+//! Walking down a pick tree until one of its leaves is picked.
+
+use std::{error::Error, fmt};
+
+use inquire::{
+    error::{InquireError, InquireResult},
+    Select,
+};
+
+use crate::{rng::Rng, LinkError, Tree};
+
+/// What a [`Picker`] decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Choice {
+    /// The option with this index
+    Option(usize),
+    /// Go back to the parent of the current node
+    Back,
+}
+
+/// Something that can choose between the children of a node
+pub trait Picker {
+    /// Chooses one of the `options`, which is never empty. `path` holds the keys
+    /// of the nodes picked so far, so it's empty at the starting node, where
+    /// going back does nothing.
+    ///
+    /// Errors stop the whole pick. In particular, [`InquireError::OperationCanceled`]
+    /// and [`InquireError::OperationInterrupted`] mean the user gave up on it.
+    fn choose(&mut self, path: &[String], options: &[Tree]) -> InquireResult<Choice>;
+}
+
+/// Asks the user to choose each option with an `inquire::Select` prompt, showing
+/// the path picked so far and letting them go back with a "← back" entry or Esc.
+/// Esc at the starting node cancels the pick.
+pub struct InteractivePicker;
+
+impl InteractivePicker {
+    const BACK: &'static str = "← back";
+    const BREADCRUMB_SEPARATOR: &'static str = " › ";
+    const HELP: &'static str = "↑↓ to move, enter to select, type to filter, esc to go back";
+}
+
+impl Picker for InteractivePicker {
+    fn choose(&mut self, path: &[String], options: &[Tree]) -> InquireResult<Choice> {
+        let breadcrumbs = path.join(Self::BREADCRUMB_SEPARATOR);
+        let mut keys: Vec<&str> = options.iter().map(|n| n.key.as_str()).collect();
+        if !path.is_empty() {
+            keys.push(Self::BACK);
+        }
+
+        let mut select = Select::new(&breadcrumbs, keys)
+            .with_vim_mode(true);
+        if !path.is_empty() {
+            select = select.with_help_message(Self::HELP);
+        }
+
+        match select.raw_prompt() {
+            Ok(res) if res.index == options.len() => Ok(Choice::Back),
+            Ok(res) => Ok(Choice::Option(res.index)),
+            Err(InquireError::OperationCanceled) if !path.is_empty() => Ok(Choice::Back),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Chooses options at random, weighted by [`Tree::weight`]
+pub struct RandomPicker {
+    rng: Rng,
+}
+
+impl RandomPicker {
+    /// A picker seeded from the current time, with different results every run
+    pub fn new() -> Self {
+        Self { rng: Rng::from_entropy() }
+    }
+
+    /// A picker whose choices are always the same for the same seed and tree
+    pub fn with_seed(seed: u64) -> Self {
+        Self { rng: Rng::with_seed(seed) }
+    }
+}
+
+impl Default for RandomPicker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Picker for RandomPicker {
+    fn choose(&mut self, _path: &[String], options: &[Tree]) -> InquireResult<Choice> {
+        let weights: Vec<f64> = options.iter().map(Tree::weight).collect();
+        Ok(Choice::Option(self.rng.weighted_index(&weights)))
+    }
+}
+
+/// Whether picking from `tree` asks the picker anything at all, as nodes with a
+/// single child are descended without asking. Links might lead to a choice, so
+/// they count as one.
+pub fn needs_choice(tree: &Tree) -> bool {
+    let mut t = tree;
+    while t.children.len() == 1 {
+        t = &t.children[0];
+    }
+    t.children.len() > 1 || t.link.is_some()
+}
+
+/// Why a pick stopped before reaching a leaf
+#[derive(Debug)]
+pub enum PickError {
+    /// The picker failed, or the user gave up on the pick
+    Prompt(InquireError),
+    /// A link on the way down couldn't be followed
+    Link(LinkError),
+}
+
+impl fmt::Display for PickError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PickError::Prompt(e) => write!(f, "{}", e),
+            PickError::Link(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for PickError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PickError::Prompt(e) => Some(e),
+            PickError::Link(e) => Some(e),
+        }
+    }
+}
+
+impl From<InquireError> for PickError {
+    fn from(e: InquireError) -> Self {
+        PickError::Prompt(e)
+    }
+}
+
+impl From<LinkError> for PickError {
+    fn from(e: LinkError) -> Self {
+        PickError::Link(e)
+    }
+}
+
+/// The node reached by taking the children at `indices`, one after the other
+fn node_at<'a>(tree: &'a mut Tree, indices: &[usize]) -> &'a mut Tree {
+    indices.iter().fold(tree, |t, &i| &mut t.children[i])
+}
+
+/// Descends the tree with `picker` until a leaf is reached. Returns the keys of
+/// every node picked along the way, from the root's child down to the leaf, or
+/// `None` if the tree has nothing to pick from.
+///
+/// Nodes with a single child have nothing to decide, so they're descended
+/// without asking the picker, and skipped when going back. The first node with
+/// something to decide is where the picker starts, so the `path` it's given
+/// leaves out the nodes above it. Links are followed
+/// as they're reached, and the linked nodes are picked from as if they were the
+/// children of the link.
+pub fn pick<P: Picker + ?Sized>(tree: &Tree, picker: &mut P) -> Result<Option<Vec<String>>, PickError> {
+    if tree.is_leaf() && tree.link.is_none() {
+        return Ok(None);
+    }
+
+    // Followed links are replaced by what they lead to in this copy
+    let mut tree = tree.clone();
+    // Indices of the nodes visited on the way down, so the picker can go back up
+    let mut indices: Vec<usize> = Vec::new();
+    let mut path = Vec::new();
+    // Length of `path` at the first node with something to decide
+    let mut start = None;
+    loop {
+        let t = node_at(&mut tree, &indices);
+        if let Some(link) = t.link.as_ref().filter(|_| t.children.is_empty()) {
+            let target = link.target.clone();
+            t.children = link.load()?.children;
+
+            // A linked tree that links to itself could be descended forever
+            let mut ancestor = &tree;
+            for &i in &indices {
+                if ancestor.link.as_ref().is_some_and(|l| l.target == target) {
+                    return Err(LinkError::Cycle { target }.into());
+                }
+                ancestor = &ancestor.children[i];
+            }
+        }
+
+        let t = node_at(&mut tree, &indices);
+        if t.is_leaf() {
+            return Ok(Some(path));
+        }
+
+        let choice = match t.children.len() {
+            1 => Choice::Option(0),
+            _ => {
+                let start = *start.get_or_insert(path.len());
+                picker.choose(&path[start..], &t.children)?
+            }
+        };
+
+        match choice {
+            Choice::Option(i) => {
+                path.push(t.children[i].key.clone());
+                indices.push(i);
+            }
+            Choice::Back => {
+                // Go back to the closest ancestor that had something to decide
+                while !indices.is_empty() {
+                    indices.pop();
+                    path.pop();
+                    if node_at(&mut tree, &indices).children.len() > 1 {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
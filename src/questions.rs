@@ -0,0 +1,156 @@
+//! Decision trees generated from attribute-tagged options.
+//!
+//! Instead of grouping options by hand, a list of options can be tagged with
+//! attributes:
+//!
+//! ```text
+//! Sushi {cuisine: japanese, price: $$, distance: far}
+//! Ramen {cuisine: japanese, price: $, distance: near}
+//! Pizza {cuisine: italian, price: $, distance: near}
+//! ```
+//!
+//! [`generate`] turns such a list into questions about the attributes, always
+//! asking first about the one that best splits the remaining options (the one
+//! with the most information gain), until a single option is left or no
+//! attribute tells the remaining options apart. Nodes asking about an attribute
+//! have keys like `cuisine: japanese`, and options without the attribute go
+//! under `cuisine: other`. Their weight is the sum of the weights of their
+//! options, so random picks are as likely to land on each option as they'd be
+//! without the questions.
+
+use std::collections::HashSet;
+
+use crate::Tree;
+
+/// Value used for options that don't have the attribute being asked about
+pub const OTHER_VALUE: &str = "other";
+
+/// A copy of `tree` where every list of tagged options, that is, every node whose
+/// children are all leaves and some of them have attributes, is replaced by the
+/// questions generated from it
+pub fn generate(tree: &Tree) -> Tree {
+    let mut tree = tree.clone();
+    let tagged = tree.children.iter().all(Tree::is_leaf)
+        && tree.children.iter().any(|c| !c.attributes.is_empty());
+
+    if tagged {
+        tree.children = questions(std::mem::take(&mut tree.children), &mut HashSet::new());
+    } else {
+        tree.children = tree.children.iter().map(generate).collect();
+    }
+    tree
+}
+
+/// The values of `attribute` among `options`, in order of appearance, and the
+/// indices of the options with each value
+fn split(options: &[Tree], attribute: &str) -> Vec<(String, Vec<usize>)> {
+    let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+    for (i, option) in options.iter().enumerate() {
+        let value = option.attribute(attribute).unwrap_or(OTHER_VALUE);
+        match groups.iter_mut().find(|(v, _)| v == value) {
+            Some((_, members)) => members.push(i),
+            None => groups.push((value.into(), vec![i])),
+        }
+    }
+    groups
+}
+
+/// How much asking about an attribute tells the options apart, assuming every
+/// option is equally likely to be picked
+fn information_gain(total: usize, groups: &[(String, Vec<usize>)]) -> f64 {
+    let entropy = |n: usize| (n as f64).log2();
+    entropy(total) - groups.iter()
+        .map(|(_, members)| members.len() as f64 / total as f64 * entropy(members.len()))
+        .sum::<f64>()
+}
+
+fn questions(options: Vec<Tree>, asked: &mut HashSet<String>) -> Vec<Tree> {
+    if options.len() <= 1 {
+        return options;
+    }
+
+    // Attributes not asked about yet, in order of appearance
+    let mut attributes: Vec<&str> = Vec::new();
+    for (name, _) in options.iter().flat_map(|o| &o.attributes) {
+        if !asked.contains(name) && !attributes.contains(&name.as_str()) {
+            attributes.push(name);
+        }
+    }
+
+    let best = attributes.into_iter()
+        .map(|a| (a, split(&options, a)))
+        .filter(|(_, groups)| groups.len() > 1)
+        .map(|(a, groups)| (information_gain(options.len(), &groups), a.to_string(), groups))
+        .fold(None, |best: Option<(f64, String, _)>, candidate| match best {
+            Some(b) if b.0 >= candidate.0 => Some(b),
+            _ => Some(candidate),
+        });
+
+    // No attribute tells the options apart, so they have to be picked directly
+    let (_, attribute, groups) = match best {
+        Some(best) => best,
+        None => return options,
+    };
+
+    let mut options: Vec<Option<Tree>> = options.into_iter().map(Some).collect();
+    asked.insert(attribute.clone());
+    let nodes = groups.into_iter()
+        .map(|(value, members)| {
+            let mut node = Tree::new(format!("{}: {}", attribute, value));
+            let members = members.iter().filter_map(|&i| options[i].take()).collect();
+            node.children = questions(members, asked);
+            node.weight = Some(node.children.iter().map(Tree::weight).sum());
+            node
+        })
+        .collect();
+    asked.remove(&attribute);
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_str;
+
+    #[test]
+    fn asks_about_the_most_telling_attribute_first() {
+        let options = parse_str("\
+Sushi {cuisine: japanese, price: $$, distance: far}
+Ramen {cuisine: japanese, price: $, distance: near}
+Pizza {cuisine: italian, price: $, distance: near}
+").unwrap();
+
+        // Every attribute splits the three options alike, so the first one wins
+        let expected = parse_str("\
+cuisine: japanese [w=2]
+    price: $$ [w=1]
+        Sushi {cuisine: japanese, price: $$, distance: far}
+    price: $ [w=1]
+        Ramen {cuisine: japanese, price: $, distance: near}
+cuisine: italian [w=1]
+    Pizza {cuisine: italian, price: $, distance: near}
+").unwrap();
+        assert_eq!(generate(&options), expected);
+    }
+
+    #[test]
+    fn untagged_options_go_under_other() {
+        let options = parse_str("\
+Sushi {cuisine: japanese} [w=3]
+Pizza {cuisine: italian} [w=0.5]
+Salad
+Soup [w=2]
+").unwrap();
+
+        let expected = parse_str("\
+cuisine: japanese [w=3]
+    Sushi {cuisine: japanese} [w=3]
+cuisine: italian [w=0.5]
+    Pizza {cuisine: italian} [w=0.5]
+cuisine: other [w=3]
+    Salad
+    Soup [w=2]
+").unwrap();
+        assert_eq!(generate(&options), expected);
+    }
+}
@@ -0,0 +1,61 @@
+//! A small, seedable pseudo-random number generator (SplitMix64). Good enough to
+//! help humans decide stuff, and reproducible given the same seed.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn with_seed(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// A generator seeded from the current time and the process' random hasher keys
+    pub fn from_entropy() -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        if let Ok(t) = SystemTime::now().duration_since(UNIX_EPOCH) {
+            hasher.write_u128(t.as_nanos());
+        }
+        Self::with_seed(hasher.finish())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number uniformly distributed in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Chooses an index with probability proportional to its weight. Falls back
+    /// to a uniform choice if no weight is positive. `weights` must not be empty.
+    pub fn weighted_index(&mut self, weights: &[f64]) -> usize {
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return (self.next_u64() % weights.len() as u64) as usize;
+        }
+
+        let mut r = self.next_f64() * total;
+        for (i, &w) in weights.iter().enumerate() {
+            if r < w {
+                return i;
+            }
+            r -= w;
+        }
+
+        // Rounding errors may leave us here, so pick the last option that could be picked
+        weights.iter().rposition(|&w| w > 0.0).unwrap()
+    }
+}
@@ -0,0 +1,103 @@
+//! Fuzzy search over every node of a pick tree, to jump straight to an option
+//! without walking down the tree level by level.
+
+use std::cmp::Reverse;
+
+use inquire::{error::InquireResult, Text};
+
+use crate::{tree::PATH_SEPARATOR, Tree};
+
+/// Scores how well `query` matches `text`, or returns `None` if it doesn't match
+/// at all. Every character in the query must appear in the text in the same
+/// order, ignoring case and whitespace in the query. Matches in a row and at the
+/// start of words score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let lower = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let mut query = query.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(lower)
+        .peekable();
+
+    let mut score = 0;
+    let mut prev: Option<char> = None;
+    let mut prev_matched = false;
+    for c in text.chars() {
+        let matched = query.peek() == Some(&lower(c));
+        if matched {
+            query.next();
+            score += 1;
+            if prev_matched {
+                score += 2;
+            }
+            if prev.is_none_or(|p| !p.is_alphanumeric()) {
+                score += 3;
+            }
+        }
+        prev_matched = matched;
+        prev = Some(c);
+    }
+
+    query.peek().is_none().then_some(score)
+}
+
+/// A node found by [`search`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match<'a> {
+    /// Keys from the root's child down to the node
+    pub path: Vec<String>,
+    pub node: &'a Tree,
+    pub score: u32,
+}
+
+/// How a node is shown in searches: its path, with a trailing separator if it's
+/// a subtree, as in `odd/3` and `odd/`
+pub fn label(path: &[String], node: &Tree) -> String {
+    let mut label = path.join(&PATH_SEPARATOR.to_string());
+    if !node.is_leaf() || node.link.is_some() {
+        label.push(PATH_SEPARATOR);
+    }
+    label
+}
+
+/// Every node in `tree` whose path matches `query`, best matches first
+pub fn search<'a>(tree: &'a Tree, query: &str) -> Vec<Match<'a>> {
+    let mut matches: Vec<Match> = tree.nodes()
+        .into_iter()
+        .filter_map(|(path, node)| {
+            let score = fuzzy_score(query, &label(&path, node))?;
+            Some(Match { path, node, score })
+        })
+        .collect();
+    matches.sort_by_key(|m| Reverse(m.score));
+    matches
+}
+
+/// Asks the user to type a query, suggesting the matching nodes, leaves or
+/// subtrees, best matches first. Submitting a query that isn't one of the
+/// suggestions takes the best match. Returns the path to the chosen node and the
+/// node itself.
+pub fn prompt(tree: &Tree) -> InquireResult<(Vec<String>, &Tree)> {
+    let suggester = |query: &str| -> Vec<String> {
+        search(tree, query).iter().map(|m| label(&m.path, m.node)).collect()
+    };
+    let validator = |query: &str| -> Result<(), String> {
+        if query.trim().is_empty() {
+            Err("Type something to search for".into())
+        } else if search(tree, query).is_empty() {
+            Err("Nothing matches that".into())
+        } else {
+            Ok(())
+        }
+    };
+
+    let query = Text::new("Search:")
+        .with_suggester(&suggester)
+        .with_validator(&validator)
+        .prompt()?;
+
+    let matches = search(tree, &query);
+    let chosen = matches.iter()
+        .find(|m| label(&m.path, m.node) == query)
+        .unwrap_or(&matches[0]);
+    Ok((chosen.path.clone(), chosen.node))
+}
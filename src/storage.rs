@@ -0,0 +1,193 @@
+//! Where pick trees are stored. Every tree is a file in a tree directory, and
+//! its identifier is the file's path relative to it, such as `lunch`, or
+//! `food/lunch` for trees in the `food` namespace, which is a subdirectory.
+//! Hidden files and directories, such as the pick history, are not trees.
+//!
+//! Trees are looked up in several directories, in the order given by a
+//! [`SearchPath`]: a `.wtp` directory in the current project, then the user's
+//! data directory, then the system's. A tree in an earlier directory shadows
+//! trees with the same identifier in later ones, so a repository can ship trees
+//! of its own.
+
+use std::{
+    env,
+    error::Error,
+    fmt, fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::tree::PATH_SEPARATOR;
+
+/// Tree identifier used when none is given
+pub const DEFAULT_TREE_ID: &str = "default";
+
+/// Name of the tree directories in the user and system data directories
+const APP_DIR: &str = "WhatToPick";
+
+/// Name of the tree directory of a project
+pub const PROJECT_DIR: &str = ".wtp";
+
+/// Environment variable that replaces the search path with a single directory
+pub const DIR_VAR: &str = "WTP_DIR";
+
+/// The user's tree directory, e.g. `~/.local/share/WhatToPick`, or `None` if
+/// there's no home directory to put it in
+pub fn data_dir() -> Option<PathBuf> {
+    directories::BaseDirs::new().map(|dirs| dirs.data_dir().join(APP_DIR))
+}
+
+/// The closest `.wtp` directory in the current directory or in its ancestors
+pub fn project_dir() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(PROJECT_DIR))
+        .find(|dir| dir.is_dir())
+}
+
+/// Tree directories shared by every user of the system, most important first
+pub fn system_dirs() -> Vec<PathBuf> {
+    if cfg!(windows) {
+        env::var_os("PROGRAMDATA").map(|d| PathBuf::from(d).join(APP_DIR)).into_iter().collect()
+    } else if cfg!(target_os = "macos") {
+        vec![PathBuf::from("/Library/Application Support").join(APP_DIR)]
+    } else {
+        // As in the XDG base directory specification
+        let data_dirs = env::var("XDG_DATA_DIRS").ok().filter(|d| !d.is_empty());
+        data_dirs.as_deref().unwrap_or("/usr/local/share:/usr/share")
+            .split(':')
+            .filter(|d| Path::new(d).is_absolute())
+            .map(|d| Path::new(d).join(APP_DIR))
+            .collect()
+    }
+}
+
+/// The directories pick trees are looked up in, in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchPath {
+    pub dirs: Vec<PathBuf>,
+    /// Where new trees and the pick history are written, if anywhere
+    pub home: Option<PathBuf>,
+}
+
+impl SearchPath {
+    /// The project's tree directory, if there is one, followed by the user's and
+    /// the system's. New trees go in the user's.
+    pub fn new() -> Self {
+        let home = data_dir();
+        let dirs = project_dir().into_iter()
+            .chain(home.clone())
+            .chain(system_dirs())
+            .collect();
+        Self { dirs, home }
+    }
+
+    /// A search path with nothing but `dir`, where new trees go as well
+    pub fn with_dir(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        Self { dirs: vec![dir.clone()], home: Some(dir) }
+    }
+
+    /// The search path set by the [`DIR_VAR`] environment variable, if it's set,
+    /// or the default one
+    pub fn from_env() -> Self {
+        match env::var_os(DIR_VAR).filter(|d| !d.is_empty()) {
+            Some(dir) => Self::with_dir(dir),
+            None => Self::new(),
+        }
+    }
+
+    /// The file of the tree identified by `id`, if any of the directories has it
+    pub fn find(&self, id: &str) -> Result<Option<PathBuf>, InvalidId> {
+        find_tree(&self.dirs, id)
+    }
+
+    /// Identifiers of the trees in every directory, sorted and without repeats
+    pub fn list_trees(&self) -> io::Result<Vec<String>> {
+        let mut ids = Vec::new();
+        for dir in &self.dirs {
+            match list_trees(dir) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                res => ids.extend(res?),
+            }
+        }
+        ids.sort();
+        ids.dedup();
+        Ok(ids)
+    }
+}
+
+impl Default for SearchPath {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The file of the tree identified by `id` in the first of `dirs` that has it
+pub fn find_tree<P: AsRef<Path>>(dirs: &[P], id: &str) -> Result<Option<PathBuf>, InvalidId> {
+    for dir in dirs {
+        let file = tree_path(dir, id)?;
+        if file.is_file() {
+            return Ok(Some(file));
+        }
+    }
+    Ok(None)
+}
+
+/// A tree identifier that doesn't name a file inside the data directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidId {
+    pub id: String,
+}
+
+impl fmt::Display for InvalidId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` isn't a valid pick tree identifier: it should be a name, or names separated by `{}`, none of them starting with `.`",
+            self.id, PATH_SEPARATOR
+        )
+    }
+}
+
+impl Error for InvalidId {}
+
+/// Path to the file of the tree identified by `id`. Identifiers that could lead
+/// outside of `dir`, like `../x` or `/x`, are rejected, and so are hidden ones.
+pub fn tree_path<P: AsRef<Path>>(dir: P, id: &str) -> Result<PathBuf, InvalidId> {
+    let mut path = dir.as_ref().to_path_buf();
+    for name in id.split(PATH_SEPARATOR) {
+        let mut components = Path::new(name).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) if !name.starts_with('.') => path.push(name),
+            _ => return Err(InvalidId { id: id.into() }),
+        }
+    }
+    Ok(path)
+}
+
+/// Identifiers of the trees in `dir` and in its subdirectories, sorted
+pub fn list_trees<P: AsRef<Path>>(dir: P) -> io::Result<Vec<String>> {
+    fn visit(dir: &Path, namespace: &str, ids: &mut Vec<String>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') {
+                continue;
+            }
+
+            let id = format!("{}{}", namespace, name);
+            let path = entry.path();
+            if path.is_file() {
+                ids.push(id);
+            } else if path.is_dir() {
+                visit(&path, &format!("{}{}", id, PATH_SEPARATOR), ids)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut ids = Vec::new();
+    visit(dir.as_ref(), "", &mut ids)?;
+    ids.sort();
+    Ok(ids)
+}
@@ -0,0 +1,162 @@
+//! Just enough TOML to read back the pick trees written by
+//! [`crate::export::to_toml`]: tables, arrays of tables and `key = value` pairs,
+//! with values written like JSON strings, numbers and booleans. Dotted keys,
+//! inline tables, dates and the like aren't supported.
+
+use crate::json::{self, SyntaxError, Value};
+
+type Table = Vec<(String, Value)>;
+
+/// Parses a TOML document
+pub fn parse(s: &str) -> Result<Value, SyntaxError> {
+    let mut root = Table::new();
+    // Names in the header of the current table, which is the last one of arrays
+    let mut current: Vec<String> = Vec::new();
+    for (i, line) in s.lines().enumerate() {
+        let text = line.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let column = line.len() - line.trim_start().len() + 1;
+        let error = |message: String| SyntaxError { message, line: i + 1, column };
+
+        if let Some(header) = text.strip_prefix('[') {
+            let (header, array) = match header.strip_prefix('[') {
+                Some(header) => (header.strip_suffix("]]"), true),
+                None => (header.strip_suffix(']'), false),
+            };
+            let path: Vec<String> = header
+                .ok_or_else(|| error("unterminated table header".into()))?
+                .split('.')
+                .map(|name| name.trim().to_owned())
+                .collect();
+            if path.iter().any(|name| !is_bare_key(name)) {
+                return Err(error("expected table names made of letters, digits, `_` and `-`".into()));
+            }
+
+            let (name, parent) = path.split_last().unwrap();
+            let parent = table(&mut root, parent).map_err(error)?;
+            match (parent.iter_mut().find(|(n, _)| n == name), array) {
+                (None, false) => parent.push((name.clone(), Value::Object(Table::new()))),
+                (None, true) => parent.push((name.clone(), Value::Array(vec![Value::Object(Table::new())]))),
+                (Some((_, Value::Array(tables))), true) => tables.push(Value::Object(Table::new())),
+                (Some(_), _) => return Err(error(format!("`{}` is defined twice", path.join(".")))),
+            }
+            current = path;
+            continue;
+        }
+
+        let (name, rest) = if text.starts_with('"') {
+            match json::parse_prefix(text).map_err(|e| e.at(i + 1, column))? {
+                (Value::String(name), rest) => (name, rest),
+                _ => unreachable!("strings start with a quote"),
+            }
+        } else {
+            let end = text.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(text.len());
+            (text[..end].to_owned(), &text[end..])
+        };
+        if !text.starts_with('"') && !is_bare_key(&name) {
+            return Err(error("expected a key made of letters, digits, `_` and `-`".into()));
+        }
+        let value = rest.trim_start()
+            .strip_prefix('=')
+            .ok_or_else(|| error("expected `key = value`".into()))?;
+
+        let value_column = column + text[..text.len() - value.len()].chars().count();
+        let (value, rest) = json::parse_prefix(value).map_err(|e| e.at(i + 1, value_column))?;
+        let rest = rest.trim_start();
+        if !rest.is_empty() && !rest.starts_with('#') {
+            return Err(error("unexpected characters after the value".into()));
+        }
+
+        let table = table(&mut root, &current).map_err(error)?;
+        if table.iter().any(|(n, _)| *n == name) {
+            return Err(error(format!("`{}` is defined twice", name)));
+        }
+        table.push((name, value));
+    }
+    Ok(Value::Object(root))
+}
+
+fn is_bare_key(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// The table with the header `path`, creating the tables along the way. Arrays
+/// of tables lead to their last table.
+fn table<'a>(root: &'a mut Table, path: &[String]) -> Result<&'a mut Table, String> {
+    let mut table = root;
+    for (i, name) in path.iter().enumerate() {
+        let value = match table.iter().position(|(n, _)| n == name) {
+            Some(index) => &mut table[index].1,
+            None => {
+                table.push((name.clone(), Value::Object(Table::new())));
+                &mut table.last_mut().unwrap().1
+            }
+        };
+        let value = match value {
+            Value::Array(tables) => tables.last_mut()
+                .ok_or_else(|| format!("`{}` is an empty array, not a table", path[..=i].join(".")))?,
+            value => value,
+        };
+        table = match value {
+            Value::Object(members) => members,
+            other => return Err(format!("`{}` is {}, not a table", path[..=i].join("."), other.kind())),
+        };
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(s: &str) -> (String, usize, usize) {
+        let e = parse(s).unwrap_err();
+        (e.message, e.line, e.column)
+    }
+
+    #[test]
+    fn reads_arrays_of_tables() {
+        let value = parse("\
+key = \"\"  # the root
+
+[[children]]
+key = \"a\"
+
+[children.attributes]
+\"x y\" = \"z\"
+
+[[children.children]]
+weight = 2
+
+[[children]]
+key = \"b\"
+").unwrap();
+        let table = |members: Vec<(&str, Value)>| {
+            Value::Object(members.into_iter().map(|(n, v)| (n.to_owned(), v)).collect())
+        };
+        assert_eq!(value, table(vec![
+            ("key", Value::String("".into())),
+            ("children", Value::Array(vec![
+                table(vec![
+                    ("key", Value::String("a".into())),
+                    ("attributes", table(vec![("x y", Value::String("z".into()))])),
+                    ("children", Value::Array(vec![table(vec![("weight", Value::Number(2.0))])])),
+                ]),
+                table(vec![("key", Value::String("b".into()))]),
+            ])),
+        ]));
+    }
+
+    #[test]
+    fn errors_tell_where_they_are() {
+        assert_eq!(error("a = 1\n  [[b]\n"), ("unterminated table header".into(), 2, 3));
+        assert_eq!(error("a = 1\na = 2\n"), ("`a` is defined twice".into(), 2, 1));
+        assert_eq!(error("a = \"b\nc = 1\n"), ("unterminated string".into(), 1, 7));
+        assert_eq!(error("a = 1\n[a]\n"), ("`a` is defined twice".into(), 2, 1));
+        assert_eq!(error("a = 1\n[a.b]\n"), ("`a` is a number, not a table".into(), 2, 1));
+        assert_eq!(error("a b = 1\n"), ("expected `key = value`".into(), 1, 1));
+        assert_eq!(error("a = 1 2\n"), ("unexpected characters after the value".into(), 1, 1));
+    }
+}
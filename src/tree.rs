@@ -0,0 +1,309 @@
+use std::{error::Error, fmt, path::Path};
+
+use crate::{
+    parser::{self, ParseError, ParseOptions},
+    storage::{self, InvalidId, SearchPath},
+};
+
+/// `name: value` tags of a node, in the order they were written
+pub type Attributes = Vec<(String, String)>;
+
+/// Separates the keys in a textual path through a tree, as in `odd/3`
+pub const PATH_SEPARATOR: char = '/';
+
+/// A node in a pick tree. The root of a tree parsed from a file has an empty key,
+/// and its children are the first options offered to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tree {
+    pub key: String,
+    pub children: Vec<Tree>,
+    /// How likely this node is to be picked at random, relative to its siblings.
+    /// Annotated in tree files as `key [w=3]`.
+    pub weight: Option<f64>,
+    /// Annotated in tree files as `key {name: value, other: value}`
+    pub attributes: Attributes,
+    /// Another tree this node stands for, written in tree files as `-> id/path`.
+    /// Linking nodes have no children until the link is followed.
+    pub link: Option<Link>,
+}
+
+impl Tree {
+    /// Weight of nodes without an explicit one
+    pub const DEFAULT_WEIGHT: f64 = 1.0;
+
+    pub fn new(key: String) -> Self {
+        Self { key, children: Vec::new(), weight: None, attributes: Vec::new(), link: None }
+    }
+
+    /// Reads a pick tree from a file in one of the directories of `dirs`, which
+    /// its `@include`s and links are looked up in. See [`parser`] for the file
+    /// format.
+    pub fn from_file(file: &Path, dirs: &SearchPath) -> Result<Self, ParseError> {
+        parser::parse_file_with(file, &ParseOptions::default().with_include_dirs(dirs.dirs.clone()))
+    }
+
+    /// Reads a pick tree from a file, with control over how strict the parser is
+    pub fn from_file_with(file: &Path, options: &ParseOptions) -> Result<Self, ParseError> {
+        parser::parse_file_with(file, options)
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    pub fn weight(&self) -> f64 {
+        self.weight.unwrap_or(Self::DEFAULT_WEIGHT)
+    }
+
+    /// Value of the attribute called `name`
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Every node in the tree but the root, in preorder, along with the keys
+    /// from the root's child down to it
+    pub fn nodes(&self) -> Vec<(Vec<String>, &Tree)> {
+        fn visit<'a>(t: &'a Tree, path: &mut Vec<String>, nodes: &mut Vec<(Vec<String>, &'a Tree)>) {
+            for child in &t.children {
+                path.push(child.key.clone());
+                nodes.push((path.clone(), child));
+                visit(child, path, nodes);
+                path.pop();
+            }
+        }
+
+        let mut nodes = Vec::new();
+        visit(self, &mut Vec::new(), &mut nodes);
+        nodes
+    }
+
+    /// The node at the end of `keys`, a path from the root's child down to it
+    pub fn get<S: AsRef<str>>(&self, keys: &[S]) -> Option<&Tree> {
+        keys.iter().try_fold(self, |t, key| {
+            t.children.iter().find(|c| c.key == key.as_ref())
+        })
+    }
+
+    /// Follows a path like `odd/3` down the tree, where each step is either the
+    /// key of a child or its index (starting at 0). Keys take precedence over
+    /// indices. Returns the keys along the way and the node the path leads to.
+    pub fn resolve(&self, path: &str) -> Result<(Vec<String>, &Tree), ResolveError> {
+        let mut keys = Vec::new();
+        let mut t = self;
+        for step in path.split(PATH_SEPARATOR).filter(|s| !s.is_empty()) {
+            let child = t.step(step).map(|i| &t.children[i]);
+            t = child.ok_or_else(|| ResolveError {
+                step: step.into(),
+                parents: keys.clone(),
+                options: t.children.iter().map(|c| c.key.clone()).collect(),
+            })?;
+            keys.push(t.key.clone());
+        }
+        Ok((keys, t))
+    }
+
+    /// A copy of this tree without the leaves in `paths`, where each path holds
+    /// the keys from the root's child down to a leaf. Nodes left without any
+    /// children by the removal are removed as well.
+    pub fn without_leaves(&self, paths: &[Vec<String>]) -> Tree {
+        let paths: Vec<&[String]> = paths.iter().map(Vec::as_slice).collect();
+        let mut tree = self.clone();
+        tree.children = Self::remove_leaves(&self.children, &paths);
+        tree
+    }
+
+    /// Index of the child `step` leads to, which is either its key or its index
+    fn step(&self, step: &str) -> Option<usize> {
+        self.children.iter()
+            .position(|c| c.key == step)
+            .or_else(|| step.parse().ok().filter(|&i: &usize| i < self.children.len()))
+    }
+
+    /// Replaces the links on the way down `path` with the nodes they lead to,
+    /// like picking does, so that leaves picked through a link can be found in
+    /// this tree, and [`Tree::resolve`] can go through them. Steps are keys or
+    /// indices, as in [`Tree::resolve`]. Links already followed are left as
+    /// they are.
+    pub fn follow_links<S: AsRef<str>>(&mut self, path: &[S]) -> Result<(), LinkError> {
+        let mut node = self;
+        for step in path {
+            if let Some(link) = node.link.as_ref().filter(|_| node.children.is_empty()) {
+                node.children = link.load()?.children;
+            }
+            match node.step(step.as_ref()) {
+                Some(i) => node = &mut node.children[i],
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces every link in the tree with the nodes it leads to, and the links
+    /// in those, and so on
+    pub fn follow_all_links(&mut self) -> Result<(), LinkError> {
+        fn follow(node: &mut Tree, targets: &mut Vec<String>) -> Result<(), LinkError> {
+            let followed = match node.link.as_ref().filter(|_| node.children.is_empty()) {
+                // A linked tree that links to itself would be followed forever
+                Some(link) if targets.contains(&link.target) => {
+                    return Err(LinkError::Cycle { target: link.target.clone() });
+                }
+                Some(link) => {
+                    node.children = link.load()?.children;
+                    targets.push(link.target.clone());
+                    true
+                }
+                None => false,
+            };
+
+            for child in &mut node.children {
+                follow(child, targets)?;
+            }
+            if followed {
+                targets.pop();
+            }
+            Ok(())
+        }
+
+        follow(self, &mut Vec::new())
+    }
+
+    fn remove_leaves(children: &[Tree], paths: &[&[String]]) -> Vec<Tree> {
+        children.iter()
+            .filter_map(|child| {
+                // Paths that go through `child`, without its key
+                let subpaths: Vec<&[String]> = paths.iter()
+                    .filter_map(|p| p.split_first())
+                    .filter(|(key, _)| **key == child.key)
+                    .map(|(_, rest)| rest)
+                    .collect();
+
+                if child.is_leaf() {
+                    let removed = subpaths.iter().any(|p| p.is_empty());
+                    return if removed { None } else { Some(child.clone()) };
+                }
+
+                let mut child = child.clone();
+                child.children = Self::remove_leaves(&child.children, &subpaths);
+                if child.is_leaf() { None } else { Some(child) }
+            })
+            .collect()
+    }
+}
+
+/// A path given to [`Tree::resolve`] leads nowhere
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveError {
+    /// The step that doesn't match any option
+    pub step: String,
+    /// Keys of the nodes before the failing step
+    pub parents: Vec<String>,
+    /// Keys of the options available at the failing step
+    pub options: Vec<String>,
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.parents.is_empty() {
+            write!(f, "there's no option `{}`", self.step)?;
+        } else {
+            let parents = self.parents.join(&PATH_SEPARATOR.to_string());
+            write!(f, "there's no option `{}` under `{}`", self.step, parents)?;
+        }
+
+        if self.options.is_empty() {
+            write!(f, ", as that's already a leaf")
+        } else {
+            let options: Vec<String> = self.options.iter().enumerate()
+                .map(|(i, key)| format!("{}: {}", i, key))
+                .collect();
+            write!(f, ". The options there are:\n    {}", options.join("\n    "))
+        }
+    }
+}
+
+impl Error for ResolveError {}
+
+/// A link to another pick tree, or to a node in it, as in `-> movies/comedy`.
+/// The linked tree is only read when the link is followed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    /// Identifier of the linked tree, optionally followed by a path in it
+    pub target: String,
+    /// Options to parse the linked tree with. The tree is looked up in their
+    /// [`ParseOptions::include_dirs`].
+    pub options: ParseOptions,
+}
+
+impl Link {
+    /// The key of a node linking to `target`, which is its last step
+    pub fn key(target: &str) -> Option<&str> {
+        target.split(PATH_SEPARATOR).rfind(|s| !s.is_empty())
+    }
+
+    /// Reads the linked tree, and returns the node the link leads to
+    pub fn load(&self) -> Result<Tree, LinkError> {
+        let steps: Vec<&str> = self.target.split(PATH_SEPARATOR).filter(|s| !s.is_empty()).collect();
+
+        // Identifiers may have separators in them, so the longest one wins
+        for len in (1..=steps.len()).rev() {
+            let id = steps[..len].join(&PATH_SEPARATOR.to_string());
+            let file = match storage::find_tree(&self.options.include_dirs, &id) {
+                Ok(Some(file)) => file,
+                // Every identifier starts with the first step, but longer ones
+                // may take in keys that can't be in one, like `.NET`
+                Err(error) if len == 1 => {
+                    return Err(LinkError::InvalidId { target: self.target.clone(), error });
+                }
+                Ok(None) | Err(_) => continue,
+            };
+
+            let tree = parser::parse_file_with(&file, &self.options)
+                .map_err(|error| LinkError::Parse { id, error })?;
+            let (_, node) = tree.resolve(&steps[len..].join(&PATH_SEPARATOR.to_string()))
+                .map_err(|error| LinkError::Resolve { target: self.target.clone(), error })?;
+            return Ok(node.clone());
+        }
+        Err(LinkError::Missing { target: self.target.clone() })
+    }
+}
+
+/// A [`Link`] couldn't be followed
+#[derive(Debug)]
+pub enum LinkError {
+    /// There's no pick tree for `target` to lead to
+    Missing { target: String },
+    /// `target` doesn't start with a valid tree identifier, as in `../x`
+    InvalidId { target: String, error: InvalidId },
+    /// The linked tree, `id`, couldn't be parsed
+    Parse { id: String, error: ParseError },
+    /// The path in `target` after the tree identifier leads nowhere
+    Resolve { target: String, error: ResolveError },
+    /// Following the link to `target` leads to another link to `target`, again
+    /// and again
+    Cycle { target: String },
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkError::Missing { target } => write!(f, "there's no pick tree for the link to `{}`", target),
+            LinkError::InvalidId { target, error } => write!(f, "the link to `{}` can't be followed: {}", target, error),
+            LinkError::Parse { id, error } => write!(f, "couldn't read the linked pick tree `{}`: {}", id, error),
+            LinkError::Resolve { target, error } => write!(f, "the link to `{}` leads nowhere: {}", target, error),
+            LinkError::Cycle { target } => write!(f, "the link to `{}` leads back to itself", target),
+        }
+    }
+}
+
+impl Error for LinkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LinkError::InvalidId { error, .. } => Some(error),
+            LinkError::Parse { error, .. } => Some(error),
+            LinkError::Resolve { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Writes pick trees back in the file format read by [`crate::parser`].
+
+use std::fmt::Write;
+
+use crate::{parser, ParseError, ParseOptions, Tree};
+
+/// Indentation used for each level when writing trees
+pub const INDENT: &str = "    ";
+
+/// Escapes the `#`s in a key that would otherwise start a comment
+fn escape_key(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    let mut after_whitespace = true;
+    for c in key.chars() {
+        if c == '#' && after_whitespace {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        after_whitespace = c.is_whitespace();
+    }
+    escaped
+}
+
+/// The line of a node, without indentation
+pub fn node_line(node: &Tree) -> String {
+    let mut line = match &node.link {
+        Some(link) => escape_key(&format!("-> {}", link.target)),
+        None => escape_key(&node.key),
+    };
+    if !node.attributes.is_empty() {
+        let attributes: Vec<String> = node.attributes.iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect();
+        write!(line, " {{{}}}", attributes.join(", ")).unwrap();
+    }
+    if let Some(weight) = node.weight {
+        write!(line, " [w={}]", weight).unwrap();
+    }
+    line
+}
+
+/// Whether `node` is read back the same from its [`node_line`]. Keys like
+/// `Salad {vegan}` or `@include x` would be read as something else.
+pub fn is_representable(node: &Tree) -> bool {
+    let parsed = parser::parse_str(&node_line(node)).ok();
+    parsed.as_ref()
+        .and_then(|p| p.children.first().filter(|_| p.children.len() == 1))
+        .is_some_and(|p| {
+            p.key == node.key && p.weight == node.weight && p.attributes == node.attributes && p.link == node.link
+        })
+}
+
+/// Writes the descendants of `tree` in the pick tree file format. The root itself
+/// isn't written, as its key is always empty in parsed trees.
+pub fn to_string(tree: &Tree) -> String {
+    fn write_children(t: &Tree, depth: usize, out: &mut String) {
+        for child in &t.children {
+            writeln!(out, "{}{}", INDENT.repeat(depth), node_line(child)).unwrap();
+            write_children(child, depth + 1, out);
+        }
+    }
+
+    let mut out = String::new();
+    write_children(tree, 0, &mut out);
+    out
+}
+
+/// Rewrites the text of a pick tree file in the canonical style: each level is
+/// indented with [`INDENT`], trailing whitespace is stripped, and runs of blank
+/// lines are squeezed into one. Comments are kept, indented like the nodes they
+/// sit among. Fails if the text can't be parsed with `options`.
+///
+/// Formatting never changes the tree: parsing the result gives the same tree as
+/// parsing `s`.
+pub fn format(s: &str, options: &ParseOptions) -> Result<String, ParseError> {
+    parser::parse_with(s.as_bytes(), options)?;
+    // The text is known to be valid, so indentation is only measured from here on
+    let lenient = ParseOptions { strict: false, ..options.clone() };
+
+    // Indentation width of the last node and of each of its ancestors
+    let mut widths: Vec<usize> = Vec::new();
+    let mut out = String::with_capacity(s.len());
+    let mut blank = false;
+    for line in s.lines() {
+        let text = line.trim();
+        if text.is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+
+        let (width, _) = parser::indentation(line, 0, &lenient, &mut None)?;
+        let depth = if parser::strip_comments(text).is_empty() {
+            widths.iter().filter(|&&w| w < width).count()
+        } else {
+            while widths.last().is_some_and(|&w| w >= width) {
+                widths.pop();
+            }
+            widths.push(width);
+            widths.len() - 1
+        };
+
+        if blank {
+            out.push('\n');
+            blank = false;
+        }
+        writeln!(out, "{}{}", INDENT.repeat(depth), text).unwrap();
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Link;
+
+    /// Mixes tabs and spaces, and has comments, runs of blank lines and `#`s
+    /// that don't start comments
+    const MESSY: &str = "\
+# Lunch
+
+
+pizza {cuisine: italian} [w=2]
+\tmargherita   
+    \\# not a comment  # but this is
+\t  # between options
+\tpepperoni
+
+
+  \t
+sushi [w=0.5]
+        nigiri
+\t\tmaki
+\\#hashtag
+C# and F#
+";
+
+    fn parse(s: &str) -> Tree {
+        parser::parse_with(s.as_bytes(), &ParseOptions::default()).unwrap()
+    }
+
+    fn format(s: &str) -> String {
+        super::format(s, &ParseOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn format_keeps_the_tree() {
+        let formatted = format(MESSY);
+        assert_eq!(parse(&formatted), parse(MESSY));
+        assert_eq!(formatted, "\
+# Lunch
+
+pizza {cuisine: italian} [w=2]
+    margherita
+    \\# not a comment  # but this is
+        # between options
+    pepperoni
+
+sushi [w=0.5]
+    nigiri
+    maki
+\\#hashtag
+C# and F#
+");
+    }
+
+    #[test]
+    fn format_is_idempotent() {
+        let formatted = format(MESSY);
+        assert_eq!(format(&formatted), formatted);
+    }
+
+    #[test]
+    fn format_rejects_invalid_trees() {
+        let options = ParseOptions::strict();
+        assert!(super::format("a\n\tb\n    c\n", &options).is_err());
+        assert!(super::format("a [w=x]\n", &options).is_err());
+    }
+
+    #[test]
+    fn to_string_round_trips() {
+        let mut pizza = Tree::new("pizza".into());
+        pizza.weight = Some(2.5);
+        pizza.attributes = vec![("cuisine".into(), "italian".into()), ("price".into(), "$$".into())];
+        pizza.children = vec![Tree::new("#1 margherita".into()), Tree::new("C# and F#".into())];
+
+        let mut linked = Tree::new("one".into());
+        linked.link = Some(Link { target: "other/one".into(), options: ParseOptions::default() });
+        linked.weight = Some(0.0);
+
+        let mut tree = Tree::new(String::new());
+        tree.children = vec![pizza, Tree::new("sushi 🍣".into()), linked];
+
+        assert_eq!(parse(&to_string(&tree)), tree);
+    }
+}
@@ -0,0 +1,276 @@
+//! Just enough YAML to read back the pick trees written by
+//! [`crate::export::to_yaml`] and similar ones: block mappings and sequences,
+//! with scalars that are plain, single-quoted, or double-quoted like JSON, and
+//! `#` comments. A document in the flow style is read as JSON. Anchors, tags,
+//! multi-line strings and the like are rejected.
+
+use crate::json::{self, SyntaxError, Value};
+
+/// A line with something other than a comment in it
+#[derive(Debug, Clone, Copy)]
+struct Line<'a> {
+    /// 1-based
+    number: usize,
+    indent: usize,
+    /// Without indentation nor trailing whitespace
+    text: &'a str,
+}
+
+impl Line<'_> {
+    fn error(&self, message: &str) -> SyntaxError {
+        SyntaxError { message: message.into(), line: self.number, column: self.indent + 1 }
+    }
+
+    /// The column where `part`, a suffix of the text, starts
+    fn column(&self, part: &str) -> usize {
+        self.indent + self.text[..self.text.len() - part.len()].chars().count() + 1
+    }
+
+    fn is_item(&self) -> bool {
+        self.text == "-" || self.text.starts_with("- ")
+    }
+}
+
+/// Parses a YAML document
+pub fn parse(s: &str) -> Result<Value, SyntaxError> {
+    let mut lines = Vec::new();
+    for (i, line) in s.lines().enumerate() {
+        let text = line.trim_start_matches(' ').trim_end();
+        let line = Line { number: i + 1, indent: line.len() - line.trim_start_matches(' ').len(), text };
+        if text.is_empty() || text.starts_with('#') || text == "---" {
+            continue;
+        }
+        if text.starts_with('\t') {
+            return Err(line.error("tabs can't be used for indentation"));
+        }
+        lines.push(line);
+    }
+
+    let first = match lines.first() {
+        Some(line) if line.text.starts_with(['{', '[']) => return json::parse(s),
+        Some(line) => *line,
+        None => return Ok(Value::Null),
+    };
+    let mut reader = Reader { lines, pos: 0 };
+    let value = reader.block(first.indent)?;
+    match reader.lines.get(reader.pos) {
+        Some(line) => Err(line.error("unexpected indentation")),
+        None => Ok(value),
+    }
+}
+
+struct Reader<'a> {
+    lines: Vec<Line<'a>>,
+    /// Index of the next line to read
+    pos: usize,
+}
+
+impl Reader<'_> {
+    /// The mapping or sequence starting at the current line, whose lines are
+    /// indented by `indent`
+    fn block(&mut self, indent: usize) -> Result<Value, SyntaxError> {
+        if self.lines[self.pos].is_item() {
+            self.sequence(indent)
+        } else {
+            self.mapping(indent)
+        }
+    }
+
+    /// The value of an entry or item with nothing after it in its line, which
+    /// is in the lines after it. `indent` is the indentation of the entry.
+    fn nested(&mut self, indent: usize, in_mapping: bool) -> Result<Value, SyntaxError> {
+        match self.lines.get(self.pos) {
+            Some(line) if line.indent > indent => self.block(line.indent),
+            // Sequences in mappings may be as indented as their key
+            Some(line) if in_mapping && line.indent == indent && line.is_item() => self.sequence(indent),
+            _ => Ok(Value::Null),
+        }
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value, SyntaxError> {
+        let mut items = Vec::new();
+        while let Some(line) = self.lines.get(self.pos).filter(|l| l.indent == indent && l.is_item()).copied() {
+            let rest = line.text[1..].trim_start();
+            if rest.is_empty() {
+                self.pos += 1;
+                items.push(self.nested(indent, false)?);
+            } else if entry(&line, rest)?.is_some() {
+                // A mapping can start right after the dash, and goes on indented
+                // as far as its first entry
+                let first = Line { indent: line.column(rest) - 1, text: rest, ..line };
+                self.lines[self.pos] = first;
+                items.push(self.mapping(first.indent)?);
+            } else {
+                self.pos += 1;
+                items.push(scalar(&line, rest)?);
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value, SyntaxError> {
+        let mut members: Vec<(String, Value)> = Vec::new();
+        while let Some(line) = self.lines.get(self.pos).filter(|l| l.indent == indent).copied() {
+            let (name, rest) = entry(&line, line.text)?
+                .ok_or_else(|| line.error("expected `name: value`"))?;
+            if members.iter().any(|(n, _)| *n == name) {
+                return Err(line.error(&format!("`{}` is there twice", name)));
+            }
+
+            self.pos += 1;
+            let value = match rest {
+                "" => self.nested(indent, true)?,
+                rest => scalar(&line, rest)?,
+            };
+            members.push((name, value));
+        }
+        Ok(Value::Object(members))
+    }
+}
+
+/// Splits `text`, a suffix of `line`, into the name and the value of a mapping
+/// entry, if it's one. The value is empty when it's in the lines after it.
+fn entry<'a>(line: &Line, text: &'a str) -> Result<Option<(String, &'a str)>, SyntaxError> {
+    let (name, rest) = match quoted(line, text)? {
+        Some((name, rest)) => match rest.trim_start().strip_prefix(':') {
+            Some(rest) => (name, rest),
+            None => return Ok(None),
+        },
+        None => match text.find(": ").or_else(|| text.strip_suffix(':').map(str::len)) {
+            Some(end) => (text[..end].trim_end().to_owned(), &text[end + 1..]),
+            None => return Ok(None),
+        },
+    };
+
+    match rest.chars().next() {
+        None => Ok(Some((name, ""))),
+        Some(c) if c.is_whitespace() => {
+            let value = rest.trim_start();
+            Ok(Some((name, if value.starts_with('#') { "" } else { value })))
+        }
+        Some(_) => Ok(None),
+    }
+}
+
+/// The string quoted at the start of `text`, a suffix of `line`, and the rest
+/// of `text`. Double-quoted strings are read like JSON ones, and single-quoted
+/// ones only escape quotes, as `''`.
+fn quoted<'a>(line: &Line, text: &'a str) -> Result<Option<(String, &'a str)>, SyntaxError> {
+    if text.starts_with('"') {
+        return match json::parse_prefix(text).map_err(|e| e.at(line.number, line.column(text)))? {
+            (Value::String(s), rest) => Ok(Some((s, rest))),
+            _ => unreachable!("strings start with a quote"),
+        };
+    }
+
+    let quoted = match text.strip_prefix('\'') {
+        Some(quoted) => quoted,
+        None => return Ok(None),
+    };
+    let mut s = String::new();
+    let mut chars = quoted.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' if chars.peek().is_some_and(|&(_, c)| c == '\'') => {
+                s.push('\'');
+                chars.next();
+            }
+            '\'' => return Ok(Some((s, &quoted[i + 1..]))),
+            c => s.push(c),
+        }
+    }
+    Err(SyntaxError { message: "unterminated string".into(), line: line.number, column: line.column("") })
+}
+
+/// The value written in `text`, a suffix of `line`, which may be followed by a
+/// comment
+fn scalar(line: &Line, text: &str) -> Result<Value, SyntaxError> {
+    let (value, rest) = if let Some((s, rest)) = quoted(line, text)? {
+        (Value::String(s), rest)
+    } else if text.starts_with(['[', '{']) {
+        json::parse_prefix(text).map_err(|e| e.at(line.number, line.column(text)))?
+    } else if text.starts_with(['&', '*', '!', '|', '>', '%', '@', '`']) {
+        let message = "anchors, aliases, tags and block scalars aren't supported".into();
+        return Err(SyntaxError { message, line: line.number, column: line.column(text) });
+    } else {
+        return Ok(plain(without_comment(text)));
+    };
+
+    // Only a comment may follow, after some whitespace
+    let after = rest.trim_start();
+    let comment = after.starts_with('#') && after.len() < rest.len();
+    if !after.is_empty() && !comment {
+        let message = "unexpected characters after the value".into();
+        return Err(SyntaxError { message, line: line.number, column: line.column(rest) });
+    }
+    Ok(value)
+}
+
+/// A plain scalar, which isn't quoted
+fn plain(text: &str) -> Value {
+    let looks_numeric = text.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '-' | '+' | '.'));
+    match text {
+        "" | "null" | "~" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => match text.parse() {
+            Ok(n) if looks_numeric => Value::Number(n),
+            _ => Value::String(text.into()),
+        },
+    }
+}
+
+/// `text` without a trailing comment, which starts with a `#` after whitespace
+fn without_comment(text: &str) -> &str {
+    let end = text.char_indices()
+        .find(|&(i, c)| c == '#' && (i == 0 || text[..i].ends_with(char::is_whitespace)))
+        .map_or(text.len(), |(i, _)| i);
+    text[..end].trim_end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(s: &str) -> (String, usize, usize) {
+        let e = parse(s).unwrap_err();
+        (e.message, e.line, e.column)
+    }
+
+    #[test]
+    fn reads_quoted_and_plain_scalars() {
+        let value = parse("\
+# a comment
+key: '#1 pepperoni'  # not part of the key
+plain: pizza  # favourite
+single: 'it''s'
+double: \"tab\\there\"
+number: 2.5
+items:
+- a
+-   b: c
+    d: []
+").unwrap();
+        assert_eq!(value, Value::Object(vec![
+            ("key".into(), Value::String("#1 pepperoni".into())),
+            ("plain".into(), Value::String("pizza".into())),
+            ("single".into(), Value::String("it's".into())),
+            ("double".into(), Value::String("tab\there".into())),
+            ("number".into(), Value::Number(2.5)),
+            ("items".into(), Value::Array(vec![
+                Value::String("a".into()),
+                Value::Object(vec![("b".into(), Value::String("c".into())), ("d".into(), Value::Array(Vec::new()))]),
+            ])),
+        ]));
+    }
+
+    #[test]
+    fn errors_tell_where_they_are() {
+        assert_eq!(error("a: 1\n  b: 2\n"), ("unexpected indentation".into(), 2, 3));
+        assert_eq!(error("a:\n  - key: 'open\n"), ("unterminated string".into(), 2, 15));
+        assert_eq!(error("a: \"b\"c\n"), ("unexpected characters after the value".into(), 1, 7));
+        assert_eq!(error("a: &anchor b\n"), ("anchors, aliases, tags and block scalars aren't supported".into(), 1, 4));
+        assert_eq!(error("a: 1\na: 2\n"), ("`a` is there twice".into(), 2, 1));
+        assert_eq!(error("a: 1\n\tb: 2\n"), ("tabs can't be used for indentation".into(), 2, 1));
+    }
+}